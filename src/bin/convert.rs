@@ -0,0 +1,48 @@
+use std::error::Error;
+use std::fmt::Write;
+use std::{env, fs};
+
+use cg_ss_25::lib::{
+    binary::{
+        Kind, LineFile, decode_header, decode_points, decode_segments, write_lines, write_segments,
+    },
+    data::write_lines_to_file,
+    segment_reader::SegmentReader,
+};
+
+// Converts segment files between the text format (.dat) and the binary format (.bin):
+//   convert ../data/01/s_100000_1.dat ../data/01/s_100000_1.bin
+//   convert ../data/01/s_100000_1.bin s_100000_1.dat
+// `convert --lines` writes the larger lines kind, which `LineFile` reads without converting.
+fn main() -> Result<(), Box<dyn Error>> {
+    let as_lines = env::args().any(|arg| arg == "--lines");
+    let args: Vec<String> = env::args().filter(|arg| arg != "--lines").collect();
+    if args.len() != 3 {
+        return Err(format!("usage: {} [--lines] <input> <output>", args[0]).into());
+    }
+    let (input, output) = (&args[1], &args[2]);
+
+    if input.ends_with(".bin") {
+        let bytes = fs::read(input)?;
+        match decode_header(&bytes, input)?.kind {
+            Kind::Segments => write_lines_to_file(output, &decode_segments(&bytes, input)?)?,
+            Kind::Lines => write_lines_to_file(output, LineFile::read(input)?.lines())?,
+            Kind::Points => {
+                let mut text = String::new();
+                for point in decode_points(&bytes, input)? {
+                    writeln!(text, "{} {}", point.x, point.y)?;
+                }
+                fs::write(output, text)?;
+            }
+        }
+    } else {
+        let lines = SegmentReader::open(input)?.collect::<Result<Vec<_>, _>>()?;
+        if as_lines {
+            write_lines(output, &lines)?;
+        } else {
+            write_segments(output, &lines)?;
+        }
+        println!("{} segments written to {}", lines.len(), output);
+    }
+    Ok(())
+}
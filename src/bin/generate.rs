@@ -0,0 +1,57 @@
+use std::env;
+use std::error::Error;
+
+use cg_ss_25::lib::{data::write_lines_to_file, generator::SegmentGenerator, point::Point};
+
+const FAMILIES: &[&str] = &[
+    "uniform",
+    "collinear",
+    "shared",
+    "vertical",
+    "grid",
+    "concurrent",
+];
+
+// Writes a reproducible segment set in the .dat format, e.g.
+//   generate uniform 1000 10 42 s_1000_10.dat
+// All segments lie in [0, 1000] x [0, 1000].
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = env::args().collect();
+    if args.len() != 6 {
+        return Err(format!(
+            "usage: {} <{}> <count> <max length> <seed> <output>",
+            args[0],
+            FAMILIES.join("|")
+        )
+        .into());
+    }
+    let count: usize = args[2].parse()?;
+    let max_length: f64 = args[3].parse()?;
+    let seed: u64 = args[4].parse()?;
+
+    let mut generator = SegmentGenerator::new(
+        seed,
+        Point { x: 0.0, y: 0.0 },
+        Point {
+            x: 1000.0,
+            y: 1000.0,
+        },
+    );
+    // grid points at a quarter of the maximal length, but not closer than 1
+    let spacing = (max_length / 4.0).max(1.0);
+    if args[1] == "grid" && spacing > max_length {
+        return Err("the grid family needs a max length of at least 1".into());
+    }
+    let lines = match args[1].as_str() {
+        "uniform" => generator.uniform(count, max_length),
+        "collinear" => generator.collinear(count, max_length),
+        "shared" => generator.shared_endpoints(count, max_length, (count / 10).max(1)),
+        "vertical" => generator.vertical(count, max_length),
+        "grid" => generator.grid_snapped(count, max_length, spacing),
+        "concurrent" => generator.concurrent(count, max_length),
+        other => return Err(format!("unknown family '{}'", other).into()),
+    };
+    write_lines_to_file(&args[5], &lines)?;
+    println!("{} segments written to {}", lines.len(), args[5]);
+    Ok(())
+}
@@ -1,9 +1,26 @@
+use std::env;
+use std::error::Error;
+use std::fs;
 use std::time::Instant;
 
-use cg_ss_25::lib::data::read_lines_from_file;
+use cg_ss_25::lib::{
+    binary::LineFile,
+    broad_phase::{grid_brute_force, parallel_brute_force},
+    line::Line,
+    segment_reader::SegmentReader,
+    wkt::Geometry,
+};
 
-fn main() {
+// `p01 --broad-phase` also runs the grid broad phase and the parallel brute force.
+// `p01 --grid` only runs the grid broad phase, which is fast enough for the s_100000 files.
+// `p01 --binary` reads the binary copies (see the `convert` binary) instead of the text files.
+// `p01 --wkt` also writes each segment set as WKT, e.g. for QGIS.
+fn main() -> Result<(), Box<dyn Error>> {
     let print_each_check = false;
+    let broad_phase = env::args().any(|arg| arg == "--broad-phase");
+    let grid_only = env::args().any(|arg| arg == "--grid");
+    let binary = env::args().any(|arg| arg == "--binary");
+    let wkt = env::args().any(|arg| arg == "--wkt");
 
     let mut files = Vec::new();
     files.push("../data/01/s_1000_1.dat");
@@ -13,11 +30,36 @@ fn main() {
 
     for file in files {
         // let lines = read_lines_from_file("../data/01/s_1000_10.dat");
-        println!(
-            "{}",
-            file.split("/").last().unwrap().split(".").next().unwrap()
-        );
-        let lines = read_lines_from_file(file);
+        let name = file.split("/").last().unwrap().split(".").next().unwrap();
+        println!("{}", name);
+        let (line_file, text);
+        let lines: &[Line] = if binary {
+            line_file = LineFile::read(&file.replace(".dat", ".bin"))?;
+            line_file.lines()
+        } else {
+            text = SegmentReader::open(file)?.collect::<Result<Vec<_>, _>>()?;
+            &text
+        };
+        if wkt {
+            fs::write(
+                format!("{}.wkt", name),
+                Geometry::from_lines(lines).to_wkt(),
+            )?;
+        }
+
+        if broad_phase || grid_only {
+            let timer = Instant::now();
+            let pairs = grid_brute_force(lines);
+            println!("Grid: {} crosses in {:?}", pairs.len(), timer.elapsed());
+        }
+        if grid_only {
+            continue;
+        }
+        if broad_phase {
+            let timer = Instant::now();
+            let pairs = parallel_brute_force(lines);
+            println!("Parallel: {} crosses in {:?}", pairs.len(), timer.elapsed());
+        }
 
         let mut number_of_crosses = 0;
 
@@ -49,6 +91,8 @@ fn main() {
         println!("Time elapsed: {:?}", timer.elapsed());
         println!("Number of crosses: {}", number_of_crosses);
     }
+
+    Ok(())
 }
 
 #[cfg(test)]
@@ -1,19 +1,22 @@
 use std::collections::HashMap;
-use std::fs::read_to_string;
+use std::env;
+use std::error::Error;
+use std::fs;
 
-use cg_ss_25::lib::point::Point;
+use cg_ss_25::lib::{
+    boolean::even_odd_polygons,
+    dcel::{Dcel, OUTER_FACE},
+    geojson::{Feature, write_features_to_file},
+    line::Line,
+    overlay::Overlay,
+    point::Point,
+    point_location::SlabDecomposition,
+    svg_reader::SvgDocument,
+    svg_writer::{Style, SvgWriter, YAxis},
+    wkt::Geometry,
+};
 
-fn read_file_rows(filename: &str) -> Vec<String> {
-    let mut result = Vec::new();
-
-    for row in read_to_string(filename).unwrap().lines() {
-        result.push(row.to_string())
-    }
-
-    result
-}
-
-fn calculate_area_polygon(points: &Vec<Point>) -> f64 {
+fn calculate_area_polygon(points: &[Point]) -> f64 {
     let mut area: f64 = 0.0;
     let point_zero = Point { x: 0.0, y: 0.0 };
     for n in 0..points.len() - 1 {
@@ -29,117 +32,27 @@ fn calculate_area_triangle(point_0: &Point, point_1: &Point, point_2: &Point) ->
     area
 }
 
-fn main() {
-    let mut states: HashMap<String, Vec<Vec<Point>>> = HashMap::new();
-    let mut cities: HashMap<String, Point> = HashMap::new();
-    let mut current_id = String::new();
+// `p02 --svg` also writes the map with areas and cities to p02.svg.
+// `p02 --geojson` also writes the states with their areas and the cities to p02.geojson.
+// `p02 --wkt` also writes the states as WKT to p02_states.csv.
+// `p02 --dcel` also builds the borders as DCEL and prints its size.
+// `p02 --overlay` also prints how much of every state lies in each cell of a 4 x 4 grid.
+fn main() -> Result<(), Box<dyn Error>> {
     let path = "../data/02/DeutschlandMitStaedten.svg";
-    let data = read_file_rows(path);
-    for mut line in data {
-        line = line.trim().to_string();
-        if line.contains("id=") && !line.contains("svg") {
-            current_id = line
-                .split("id=")
-                .nth(1)
-                .unwrap()
-                .split(" ")
-                .next()
-                .unwrap()
-                .replace("\"", "")
-                .parse()
-                .unwrap();
-            if line.starts_with("id=") {
-                cities.insert(current_id.clone(), Point { x: 0.0, y: 0.0 });
-            } else {
-                states.insert(current_id.clone(), Vec::new());
-            }
-        }
-        if line.starts_with("M") {
-            states.get_mut(&current_id).unwrap().push(Vec::new());
-            let coords = line[1..].split(",").collect::<Vec<&str>>();
-            let point = Point {
-                x: coords[0].parse().unwrap(),
-                y: coords[1].parse().unwrap(),
-            };
-            if let Some(state_vec) = states.get_mut(&current_id) {
-                if let Some(last_vec) = state_vec.last_mut() {
-                    last_vec.push(point);
-                }
-            }
-        }
-        if line.starts_with("l") {
-            let coords = line[1..].split(",").collect::<Vec<&str>>();
-            let last_point = states
-                .get(&current_id)
-                .unwrap()
-                .last()
-                .unwrap()
-                .last()
-                .unwrap();
-            let point = Point {
-                x: last_point.x + coords[0].parse::<f64>().unwrap(),
-                y: last_point.y + coords[1].parse::<f64>().unwrap(),
-            };
-            if let Some(state_vec) = states.get_mut(&current_id) {
-                if let Some(last_vec) = state_vec.last_mut() {
-                    last_vec.push(point);
-                }
-            }
-        }
-        if line.starts_with("L") {
-            let coords = line[1..].split(",").collect::<Vec<&str>>();
-            let point = Point {
-                x: coords[0].parse().unwrap(),
-                y: coords[1].parse().unwrap(),
-            };
-            if let Some(last_vec) = states.get_mut(&current_id) {
-                if let Some(last_vec) = last_vec.last_mut() {
-                    last_vec.push(point);
-                }
-            }
-        }
-        if line.starts_with("H") {
-            let coords = line[1..].split(",").collect::<Vec<&str>>();
-            let last_point = states
-                .get(&current_id)
-                .unwrap()
-                .last()
-                .unwrap()
-                .last()
-                .unwrap();
-            let point = Point {
-                x: coords[0].parse().unwrap(),
-                y: last_point.y,
-            };
-            if let Some(last_vec) = states.get_mut(&current_id) {
-                if let Some(last_vec) = last_vec.last_mut() {
-                    last_vec.push(point);
-                }
-            }
-        }
-        if line.starts_with("sodipodi:cx") {
-            if let Some(point) = cities.get_mut(&current_id) {
-                point.x = line
-                    .split("=")
-                    .nth(1)
-                    .unwrap()
-                    .replace("\"", "")
-                    .parse()
-                    .unwrap();
-            }
-        }
-        if line.starts_with("sodipodi:cy") {
-            if let Some(point) = cities.get_mut(&current_id) {
-                point.y = line
-                    .split("=")
-                    .nth(1)
-                    .unwrap()
-                    .replace("\"", "")
-                    .parse()
-                    .unwrap();
-            }
-        }
+    let document = SvgDocument::from_file(path)?;
+    // states are paths, cities are Inkscape circles
+    let states: HashMap<String, Vec<Vec<Point>>> =
+        document.labeled_polygons().into_iter().collect();
+    let cities: HashMap<String, Point> = document.labeled_points().into_iter().collect();
+
+    // the map as vector overlay: states with their areas, cities with their names
+    let mut overlay = SvgWriter::new(YAxis::Down);
+    let state_layer = overlay.layer("states", Style::stroke("gray", 0.5).with_fill("#eeeeee"));
+    for rings in states.values() {
+        state_layer.polygon(rings);
     }
+    let mut area_labels = Vec::new();
+    let mut features = Vec::new();
 
     for (state, vec) in &states {
         let mut area = 0.0;
@@ -176,33 +89,146 @@ fn main() {
             bounding_area += (max_x - min_x) * (max_y - min_y);
         }
         println!("{} {:.3?} ({:.3?})", state, area, bounding_area);
+        features.push(
+            Feature::new(Geometry::MultiPolygon(even_odd_polygons(vec.clone())))
+                .with_id(state)
+                .with_property("area", area)
+                .with_property("bounding_area", bounding_area),
+        );
+        if let Some(outline) = vec.first() {
+            let center = outline
+                .iter()
+                .skip(1)
+                .fold(Point { x: 0.0, y: 0.0 }, |sum, p| sum + *p);
+            let n = (outline.len() - 1).max(1) as f64;
+            area_labels.push((
+                Point {
+                    x: center.x / n,
+                    y: center.y / n,
+                },
+                format!("{} {:.0}", state, area),
+            ));
+        }
+    }
+    let area_layer = overlay.layer("areas", Style::fill("navy").with_font_size(8.0));
+    for (position, text) in &area_labels {
+        area_layer.label(position, text);
     }
 
     println!();
 
+    if env::args().any(|arg| arg == "--dcel") {
+        print_dcel(&states);
+    }
+
+    if env::args().any(|arg| arg == "--overlay") {
+        print_grid_overlay(&states);
+    }
+
+    // build the index once, then every city is a O(log n) lookup
+    let state_index = SlabDecomposition::new(
+        states
+            .iter()
+            .map(|(state, rings)| (state.clone(), rings.clone())),
+    );
     for (city, point) in &cities {
-        let mut state_of_city = String::new();
-        let mut possible_states: Vec<String> = Vec::new();
-        for (state, vec) in &states {
-            for points in vec.iter() {
-                if point.is_in_polygon(points) {
-                    possible_states.push(state.clone());
-                }
-            }
-        }
-        let mut state_occurrences: HashMap<String, usize> = HashMap::new();
-        for state in possible_states.clone() {
-            *state_occurrences.entry(state.clone()).or_insert(0) += 1;
+        let state_of_city = state_index.locate(point).cloned().unwrap_or_default();
+        println!("{} ({})", city, state_of_city);
+        features.push(
+            Feature::new(Geometry::Point(*point))
+                .with_id(city)
+                .with_property("state", state_of_city),
+        );
+    }
+
+    let city_layer = overlay.layer(
+        "cities",
+        Style::fill("red")
+            .with_point_radius(2.0)
+            .with_font_size(6.0),
+    );
+    for (city, point) in &cities {
+        city_layer.point(point).label(point, city);
+    }
+    if env::args().any(|arg| arg == "--svg") {
+        overlay.save("p02.svg")?;
+    }
+
+    // the states as WKT, e.g. for QGIS ("delimited text" with geometry field `wkt`)
+    if env::args().any(|arg| arg == "--wkt") {
+        let mut csv = String::from("id,wkt\n");
+        for (state, rings) in &states {
+            let geometry = Geometry::MultiPolygon(even_odd_polygons(rings.clone()));
+            csv.push_str(&format!("{},\"{}\"\n", state, geometry.to_wkt()));
         }
-        if state_occurrences.len() == 1 {
-            state_of_city = possible_states[0].clone();
-        } else {
-            for (state, value) in state_occurrences {
-                if value == 1 {
-                    state_of_city = state.clone();
-                }
-            }
+        fs::write("p02_states.csv", csv)?;
+    }
+
+    // states with their areas and cities with their state
+    if env::args().any(|arg| arg == "--geojson") {
+        write_features_to_file("p02.geojson", &features)?;
+    }
+
+    Ok(())
+}
+
+// The borders as planar subdivision, shared borders become one edge.
+fn print_dcel(states: &HashMap<String, Vec<Vec<Point>>>) {
+    let borders: Vec<Line> = states
+        .values()
+        .flatten()
+        .flat_map(|ring| ring.windows(2).map(|w| Line::new(w[0], w[1])))
+        .collect();
+    let map = Dcel::from_segments(&borders);
+    let bounded_area: f64 = (0..map.faces.len())
+        .filter(|&face| face != OUTER_FACE)
+        .map(|face| map.area(face))
+        .sum();
+    println!(
+        "DCEL: {} vertices, {} edges, {} faces, bounded area {:.3}",
+        map.vertices.len(),
+        map.edge_count(),
+        map.faces.len(),
+        bounded_area
+    );
+    println!();
+}
+
+// How much of every state lies in each cell of a 4 x 4 grid over the map.
+fn print_grid_overlay(states: &HashMap<String, Vec<Vec<Point>>>) {
+    let points = || states.values().flatten().flatten();
+    let (min_x, max_x) = points().fold((f64::MAX, f64::MIN), |(a, b), p| (a.min(p.x), b.max(p.x)));
+    let (min_y, max_y) = points().fold((f64::MAX, f64::MIN), |(a, b), p| (a.min(p.y), b.max(p.y)));
+    let cells = 4;
+    let (width, height) = (
+        (max_x - min_x) / cells as f64,
+        (max_y - min_y) / cells as f64,
+    );
+    let mut grid = Vec::new();
+    for column in 0..cells {
+        for row in 0..cells {
+            let (x, y) = (min_x + column as f64 * width, min_y + row as f64 * height);
+            let cell = [
+                (x, y),
+                (x + width, y),
+                (x + width, y + height),
+                (x, y + height),
+                (x, y),
+            ];
+            let ring = cell.iter().map(|&(x, y)| Point { x, y }).collect();
+            grid.push(((column, row), vec![ring]));
         }
-        println!("{} ({})", city, state_of_city);
     }
+    let cell_overlay = Overlay::new(states.iter().map(|(s, r)| (s.clone(), r.clone())), grid);
+    for (state, areas) in cell_overlay.first.iter().zip(cell_overlay.area_matrix()) {
+        let shares: Vec<String> = cell_overlay
+            .second
+            .iter()
+            .zip(areas)
+            .filter(|(_, area)| *area > 0.0)
+            .map(|((column, row), area)| format!("({}, {}) {:.3}", column, row, area))
+            .collect();
+        println!("{}: {}", state, shares.join(", "));
+    }
+    println!();
 }
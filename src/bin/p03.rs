@@ -1,147 +1,26 @@
-use cg_ss_25::lib::ccw::crossing_point;
-use cg_ss_25::lib::event::EventType;
-use cg_ss_25::lib::event::EventType::{End, Intersection, Start};
-use cg_ss_25::lib::line_with_ord::LineWithOrd;
 use cg_ss_25::lib::{
-    common::EPSILON,
-    data::read_lines_from_file,
-    event::{Event, EventHeap},
+    data::DataError,
+    geojson::{Feature, write_features_to_file},
+    segment_reader::SegmentReader,
+    svg_writer::{Style, SvgWriter, YAxis},
+    sweep::{Intersection, try_intersections_with},
+    sweep_status::{BTreeStatus, SkipListStatus, SweepStatus, TreapStatus},
+    verify::{DiscrepancyKind, Report, try_cross_check},
+    wkt::Geometry,
 };
-use std::collections::{BTreeSet, BinaryHeap};
+use std::env;
+use std::error::Error;
 use std::time::Instant;
 
-fn treat_left_endpoint(
-    event: &Event,
-    sweep_line: &mut BTreeSet<LineWithOrd>,
-    event_queue: &mut BinaryHeap<Event>,
-    current_x: f64,
-) {
-    let line = event.line.clone();
-    let current_seg = LineWithOrd::new(line.clone(), current_x);
-
-    sweep_line.insert(current_seg.clone());
-
-    let above = sweep_line.range(current_seg.clone()..).nth(1);
-    let below = sweep_line.range(..current_seg.clone()).next_back();
-
-    if let Some(above_seg) = above {
-        if line.crosses(&above_seg.line) {
-            let p = crossing_point(&line.p1, &line.p2, &above_seg.line.p1, &above_seg.line.p2);
-            if p.x > current_x {
-                event_queue.push(Event::new(
-                    p.x,
-                    line.clone(),
-                    Intersection(p, line.clone(), above_seg.line.clone()),
-                ));
-            }
-        }
-    }
-
-    if let Some(below_seg) = below {
-        if line.crosses(&below_seg.line) {
-            let p = crossing_point(&line.p1, &line.p2, &below_seg.line.p1, &below_seg.line.p2);
-            if p.x > current_x {
-                event_queue.push(Event::new(
-                    p.x,
-                    line.clone(),
-                    Intersection(p, line.clone(), below_seg.line.clone()),
-                ));
-            }
-        }
-    }
-}
-
-fn treat_right_endpoint(
-    event: &Event,
-    sweep_line: &mut BTreeSet<LineWithOrd>,
-    event_queue: &mut BinaryHeap<Event>,
-    current_x: f64,
-) {
-    let line = event.line.clone();
-    let seg = LineWithOrd::new(line.clone(), current_x);
-
-    let above = sweep_line.range(seg.clone()..).nth(1).cloned();
-    let below = sweep_line.range(..seg.clone()).next_back().cloned();
-
-    sweep_line.remove(&seg);
-
-    if let (Some(above_seg), Some(below_seg)) = (above, below) {
-        if above_seg.line.crosses(&below_seg.line) {
-            let p = crossing_point(
-                &above_seg.line.p1,
-                &above_seg.line.p2,
-                &below_seg.line.p1,
-                &below_seg.line.p2,
-            );
-            if p.x > current_x {
-                event_queue.push(Event::new(
-                    p.x,
-                    above_seg.line.clone(),
-                    EventType::Intersection(p, line.clone(), above_seg.line.clone()),
-                ));
-            }
-        }
-    }
-}
-
-fn treat_intersection(
-    event: &Event,
-    sweep_line: &mut BTreeSet<LineWithOrd>,
-    event_queue: &mut BinaryHeap<Event>,
-    current_x: f64,
-) {
-    let current_line = event.line.clone();
-    let current_seg = LineWithOrd::new(current_line.clone(), current_x);
-
-    let above = sweep_line.range(current_seg.clone()..).nth(1).cloned();
-    let below = sweep_line.range(..current_seg.clone()).next_back().cloned();
-
-    sweep_line.remove(&current_seg);
-    if let Some(ref above_seg) = above {
-        sweep_line.remove(above_seg);
-    }
-
-    if let Some(above_seg) = &above {
-        let mut seg1 = current_seg.clone();
-        seg1.sweep_x = current_x + EPSILON;
-
-        let mut seg2 = above_seg.clone().clone();
-        seg2.sweep_x = current_x + EPSILON;
-
-        sweep_line.insert(seg2.clone());
-        sweep_line.insert(seg1.clone());
-
-        let new_above = sweep_line.range(seg2.clone()..).nth(1);
-        if let Some(a) = new_above {
-            if seg2.line.crosses(&a.line) {
-                let p = crossing_point(&seg2.line.p1, &seg2.line.p2, &a.line.p1, &a.line.p2);
-                if p.x > current_x {
-                    event_queue.push(Event::new(
-                        p.x,
-                        seg2.line.clone(),
-                        EventType::Intersection(p, seg2.line.clone(), seg1.line.clone()),
-                    ));
-                }
-            }
-        }
-
-        let new_below = sweep_line.range(..seg1.clone()).next_back();
-        if let Some(b) = new_below {
-            if seg1.line.crosses(&b.line) {
-                let p = crossing_point(&seg1.line.p1, &seg1.line.p2, &b.line.p1, &b.line.p2);
-                if p.x > current_x {
-                    event_queue.push(Event::new(
-                        p.x,
-                        seg1.line.clone(),
-                        EventType::Intersection(p, seg1.line.clone(), seg2.line.clone()),
-                    ));
-                }
-            }
-        }
-    }
-}
-
-fn main() {
+// `p03 --bench` runs the sweep with every status structure instead of only the B-tree.
+// `p03 --svg` also draws the segments and their intersections to <name>.svg.
+// `p03 --geojson` also writes the intersections to <name>_intersections.geojson.
+// `p03 --verify` also runs the brute force of p01 and lists the pairs only one method found.
+fn main() -> Result<(), Box<dyn Error>> {
+    let bench = env::args().any(|arg| arg == "--bench");
+    let verify = env::args().any(|arg| arg == "--verify");
+    let svg = env::args().any(|arg| arg == "--svg");
+    let geojson = env::args().any(|arg| arg == "--geojson");
     let mut files = Vec::new();
     files.push("../data/01/s_1000_1.dat");
     files.push("../data/01/s_10000_1.dat");
@@ -149,49 +28,92 @@ fn main() {
     files.push("../data/01/s_1000_10.dat");
 
     for file in files {
-        let mut intersection_count = 0;
-        println!(
-            "{}",
-            file.split("/").last().unwrap().split(".").next().unwrap()
-        );
-        let lines = read_lines_from_file(file);
+        let name = file.split("/").last().unwrap().split(".").next().unwrap();
+        println!("{}", name);
+        // every pass streams the segments from the file, only the sweep keeps them in memory
+        let (found, count) = benchmark::<BTreeStatus>("btree", file)?;
+        if bench {
+            benchmark::<SkipListStatus>("skip list", file)?;
+            benchmark::<TreapStatus>("treap", file)?;
+        }
+        println!("number of segments: {}", count);
 
-        // The BinaryHeap is a min heap
-        // => It is sorted while inserting and always pops the smallest element first
-        let mut event_queue: EventHeap = BinaryHeap::new();
-        let mut sweep_line: BTreeSet<LineWithOrd> = BTreeSet::new();
+        if verify {
+            print_cross_check(try_cross_check(SegmentReader::open(file)?)?);
+        }
 
-        let mut number_of_segments = 0;
-        for line in &lines {
-            if (line.p1.x - line.p2.x).abs() < EPSILON {
-                continue;
+        if svg {
+            let mut overlay = SvgWriter::new(YAxis::Up);
+            let segments = overlay.layer("segments", Style::stroke("black", 0.5));
+            for line in SegmentReader::open(file)? {
+                segments.line(&line?);
             }
-            number_of_segments += 1;
-            let (left, right) = if line.p1.x < line.p2.x {
-                (&line.p1, &line.p2)
-            } else {
-                (&line.p2, &line.p1)
-            };
-            event_queue.push(Event::new(left.x, line.clone(), Start));
-            event_queue.push(Event::new(right.x, line.clone(), End));
+            let points: Vec<_> = found
+                .iter()
+                .map(|intersection| intersection.point)
+                .collect();
+            overlay
+                .layer("intersections", Style::fill("red").with_point_radius(1.5))
+                .points(&points);
+            overlay.save(&format!("{}.svg", name))?;
         }
 
-        let start_time = Instant::now();
-        while let Some(event) = event_queue.pop() {
-            let current_x = event.x.0.into_inner();
-            match &event.event_type {
-                Start => treat_left_endpoint(&event, &mut sweep_line, &mut event_queue, current_x),
-                End => treat_right_endpoint(&event, &mut sweep_line, &mut event_queue, current_x),
-                Intersection(p, l1, l2) => {
-                    intersection_count += 1;
-                    treat_intersection(&event, &mut sweep_line, &mut event_queue, current_x)
-                }
-            }
+        if geojson {
+            let features: Vec<Feature> = found
+                .iter()
+                .map(|intersection| {
+                    Feature::new(Geometry::Point(intersection.point)).with_property(
+                        "segments",
+                        vec![intersection.segments.0, intersection.segments.1],
+                    )
+                })
+                .collect();
+            write_features_to_file(&format!("{}_intersections.geojson", name), &features)?;
         }
-        let duration = start_time.elapsed();
+    }
 
-        println!("intersection count is {}", intersection_count);
-        println!("time: {:?}", duration);
-        println!("number of segments: {}", number_of_segments);
+    Ok(())
+}
+
+fn print_cross_check(report: Report) {
+    println!(
+        "brute force: {}, sweep: {}, discrepancies: {}",
+        report.brute_force,
+        report.sweep,
+        report.discrepancies.len()
+    );
+    for kind in [
+        DiscrepancyKind::Touching,
+        DiscrepancyKind::Collinear,
+        DiscrepancyKind::Vertical,
+        DiscrepancyKind::NearParallel,
+        DiscrepancyKind::Other,
+    ] {
+        let count = report.count(kind);
+        if count > 0 {
+            println!("  {}: {}", kind, count);
+        }
+    }
+    for discrepancy in &report.discrepancies {
+        println!("  {}", discrepancy);
     }
 }
+
+// The intersections and the number of segments. The time includes reading the file.
+fn benchmark<S: SweepStatus>(
+    backend: &str,
+    file: &str,
+) -> Result<(Vec<Intersection>, usize), DataError> {
+    let mut count = 0;
+    let lines = SegmentReader::open(file)?.inspect(|line| count += line.is_ok() as usize);
+    let start_time = Instant::now();
+    let found = try_intersections_with::<S, _>(lines)?;
+    let duration = start_time.elapsed();
+    println!(
+        "{}: intersection count is {}, time: {:?}",
+        backend,
+        found.len(),
+        duration
+    );
+    Ok((found, count))
+}
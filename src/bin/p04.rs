@@ -1,11 +1,15 @@
+use cg_ss_25::lib::rbox::{self, rbox_text};
 use plotters::prelude::*;
 use std::{
+    env, fs,
+    io::Write,
     process::{Command, Stdio},
     str,
 };
 
 const TWO: i32 = 2;
 const OUT_PATH: &str = "./plots";
+const POINTS_PATH: &str = "./plots/points";
 const REPETITIONS: i32 = 5;
 const MIN_NUM_POINT_EXPONENT: i32 = 4;
 const MAX_NUM_POINT_EXPONENT: i32 = 11;
@@ -56,23 +60,54 @@ struct Distribution<'a> {
     flag: char,
 }
 
-fn point_generator(distribution_flag: char, n_points: i32, dimension: i32) -> std::process::Child {
-    // generate random points
-    let points_stream = Command::new("rbox")
+// The native generator gives the same points everywhere, with `--rbox` the installed rbox is
+// used instead.
+fn point_generator(
+    distribution_flag: char,
+    n_points: i32,
+    dimension: i32,
+    seed: u64,
+    external: bool,
+) -> String {
+    if !external {
+        return rbox_text(
+            rbox::Distribution::from_flag(distribution_flag).expect("unknown rbox distribution"),
+            n_points as usize,
+            dimension as usize,
+            seed,
+        )
+        .expect("rbox distribution failed");
+    }
+    let output = Command::new("rbox")
         .arg(n_points.to_string())
         .arg(format!("D{}", dimension))
         .arg(distribution_flag.to_string())
-        .stdout(Stdio::piped())
-        .spawn()
+        .arg(format!("t{}", seed))
+        .output()
         .expect("rbox command failed to start");
-    points_stream
+    String::from_utf8(output.stdout).expect("rbox output is not UTF-8")
 }
 
-fn measure_qhull_runtime(points_stream: std::process::Child) -> f64 {
-    let qhull = Command::new("qhull")
-        .stdin(Stdio::from(points_stream.stdout.unwrap()))
+fn qhull_is_installed() -> bool {
+    Command::new("qhull")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok()
+}
+
+fn measure_qhull_runtime(points: &str) -> f64 {
+    let mut qhull = Command::new("qhull")
+        .stdin(Stdio::piped())
         .stdout(Stdio::piped())
         .spawn()
+        .expect("qhull command failed to start");
+    qhull
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(points.as_bytes())
         .unwrap();
     let output = qhull.wait_with_output().unwrap();
     let result = str::from_utf8(&output.stdout).unwrap();
@@ -169,6 +204,13 @@ fn save_plot(
 
 fn main() {
     let n_point_powers: Vec<i32> = (MIN_NUM_POINT_EXPONENT..MAX_NUM_POINT_EXPONENT).collect();
+    let external_rbox = env::args().any(|arg| arg == "--rbox");
+    // without qhull the point sets are only written, to time qhull on another machine
+    let qhull = qhull_is_installed();
+    if !qhull {
+        println!("qhull is not installed, writing the point sets to {POINTS_PATH} instead.");
+        fs::create_dir_all(POINTS_PATH).expect("could not create the output directory");
+    }
 
     for distribution in DISTRIBUTIONS.iter() {
         let mut distribution_results: Vec<TestResult> = Vec::new();
@@ -182,10 +224,24 @@ fn main() {
             for n_point_power in n_point_powers.clone().into_iter() {
                 let n_points = TWO.pow(n_point_power as u32);
                 print!("Points: {:?} | ", n_points);
-                for _ in 0..REPETITIONS {
-                    let points_stream = point_generator(distribution.flag, n_points, *dimension);
+                for repetition in 0..REPETITIONS {
+                    let points = point_generator(
+                        distribution.flag,
+                        n_points,
+                        *dimension,
+                        repetition as u64,
+                        external_rbox,
+                    );
+                    if !qhull {
+                        let path = format!(
+                            "{POINTS_PATH}/{}_D{}_{}_t{}.txt",
+                            distribution.flag, dimension, n_points, repetition
+                        );
+                        fs::write(&path, points).expect("could not write the point set");
+                        continue;
+                    }
 
-                    let time = measure_qhull_runtime(points_stream);
+                    let time = measure_qhull_runtime(&points);
                     print!("{:?}s ", time);
 
                     distribution_results.push(TestResult {
@@ -198,7 +254,9 @@ fn main() {
             }
             print!("\n\n");
         }
-        save_plot(distribution.name, distribution_results, &n_point_powers);
+        if qhull {
+            save_plot(distribution.name, distribution_results, &n_point_powers);
+        }
         print!("\n\n\n");
     }
 }
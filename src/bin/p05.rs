@@ -1,7 +1,15 @@
-use cg_ss_25::lib::{data::read_polygon_from_file, line::Line, point::Point};
+use cg_ss_25::lib::{
+    data::read_polygon_from_file,
+    line::Line,
+    point::Point,
+    polygon::Polygon,
+    shamos_hoey::ring_self_intersection,
+    svg_writer::{Style, SvgWriter, YAxis},
+};
 use plotters::prelude::*;
 
 use good_lp::{Solution, SolverModel, constraint, default_solver, variables};
+use std::env;
 use std::error::Error;
 
 // const FILE_NAME: &str = "testpolygon.txt";
@@ -9,10 +17,27 @@ const FILE_NAME: &str = "polygon.txt";
 
 const PATH: &str = "../data/05/";
 
+// `p05 --svg` also writes the polygon and the circle as vector graphic to testpolygon.svg.
 fn main() -> Result<(), Box<dyn Error>> {
     let file_path = PATH.to_string() + FILE_NAME;
 
-    let mut points = read_polygon_from_file(&file_path);
+    let mut points = read_polygon_from_file(&file_path)?;
+
+    // the kernel and the circle below assume a simple polygon
+    if let Some(intersection) = ring_self_intersection(&points) {
+        println!(
+            "Polygon is not simple: edges {} and {} meet at ({}, {})",
+            intersection.segments.0,
+            intersection.segments.1,
+            intersection.point.x,
+            intersection.point.y
+        );
+    }
+
+    match Polygon::new(points.clone()).kernel() {
+        Some(kernel) => println!("Polygon is star-shaped, kernel area: {:.2}", kernel.area()),
+        None => println!("Polygon is not star-shaped"),
+    }
 
     // Calculate average over all vertices using map reduce
     let center = points
@@ -71,6 +96,21 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     plot_results(&points, &lines, circle_center, radius)?;
 
+    // same picture as vector graphic, in the original coordinates
+    let original: Vec<Point> = points.iter().map(|&p| p + center).collect();
+    let mut overlay = SvgWriter::new(YAxis::Up);
+    overlay
+        .layer("polygon", Style::stroke("black", 1.0))
+        .polygon(std::slice::from_ref(&original))
+        .points(&original[1..]);
+    overlay
+        .layer("inscribed circle", Style::stroke("red", 1.0))
+        .circle(&(circle_center + center), radius)
+        .point(&(circle_center + center));
+    if env::args().any(|arg| arg == "--svg") {
+        overlay.save("testpolygon.svg")?;
+    }
+
     Ok(())
 }
 
@@ -1,9 +1,34 @@
 pub mod lib {
+    pub mod binary;
+    pub mod boolean;
+    pub mod broad_phase;
     pub mod ccw;
     pub mod common;
     pub mod data;
+    pub mod dcel;
     pub mod event;
+    pub mod generator;
+    pub mod geojson;
+    pub mod half_plane;
     pub mod line;
     pub mod line_with_ord;
+    pub mod mesh;
+    pub mod minkowski;
+    pub mod overlay;
     pub mod point;
+    pub mod point_location;
+    pub mod polygon;
+    pub mod rbox;
+    pub mod red_blue;
+    pub mod segment_reader;
+    pub mod shamos_hoey;
+    pub mod simplify;
+    pub mod svg_reader;
+    pub mod svg_writer;
+    pub mod sweep;
+    pub mod sweep_status;
+    pub mod triangulation;
+    pub mod verify;
+    pub mod visibility;
+    pub mod wkt;
 }
@@ -0,0 +1,383 @@
+use crate::lib::{data::DataError, line::Line, point::Point};
+use std::{
+    fs::{self, File},
+    io::Read,
+    slice,
+};
+
+// Binary container for point and segment sets, all numbers little-endian:
+//
+//   magic    8 bytes  "CGSS25\0\0"
+//   version  u32      currently 1
+//   kind     u32      1 = points (x y), 2 = segments (x1 y1 x2 y2),
+//                     3 = lines (x1 y1 x2 y2 nx ny a, the memory layout of `Line`)
+//   count    u64      number of points / segments
+//   bbox     4 x f64  min x, min y, max x, max y
+//   payload  count x 2, 4 or 7 x f64
+//
+// Loading is a single read plus a conversion of fixed-size records, no text parsing. Segments
+// are the compact format. Lines trade almost twice the size for not converting at all:
+// `LineFile` checks the records against their endpoints once and returns them as a slice.
+pub const MAGIC: [u8; 8] = *b"CGSS25\0\0";
+pub const VERSION: u32 = 1;
+pub const HEADER_SIZE: usize = 56;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kind {
+    Points = 1,
+    Segments = 2,
+    Lines = 3,
+}
+
+impl Kind {
+    fn values_per_record(self) -> usize {
+        match self {
+            Kind::Points => 2,
+            Kind::Segments => 4,
+            Kind::Lines => 7,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Header {
+    pub version: u32,
+    pub kind: Kind,
+    pub count: u64,
+    pub min: Point,
+    pub max: Point,
+}
+
+// `Line` is read from the payload as it is
+const _: () = assert!(size_of::<Line>() == 7 * size_of::<f64>());
+const _: () = assert!(HEADER_SIZE.is_multiple_of(align_of::<Line>()));
+
+pub fn encode_points(points: &[Point]) -> Vec<u8> {
+    encode(
+        Kind::Points,
+        points.len(),
+        points.iter().copied(),
+        points.iter().flat_map(|p| [p.x, p.y]),
+    )
+}
+
+pub fn encode_segments(lines: &[Line]) -> Vec<u8> {
+    encode(
+        Kind::Segments,
+        lines.len(),
+        lines.iter().flat_map(|l| [l.p1, l.p2]),
+        lines.iter().flat_map(|l| [l.p1.x, l.p1.y, l.p2.x, l.p2.y]),
+    )
+}
+
+pub fn encode_lines(lines: &[Line]) -> Vec<u8> {
+    encode(
+        Kind::Lines,
+        lines.len(),
+        lines.iter().flat_map(|l| [l.p1, l.p2]),
+        lines
+            .iter()
+            .flat_map(|l| [l.p1.x, l.p1.y, l.p2.x, l.p2.y, l.n.x, l.n.y, l.a]),
+    )
+}
+
+pub fn write_points(path: &str, points: &[Point]) -> Result<(), DataError> {
+    fs::write(path, encode_points(points)).map_err(|e| error(path, e.to_string()))
+}
+
+pub fn write_segments(path: &str, lines: &[Line]) -> Result<(), DataError> {
+    fs::write(path, encode_segments(lines)).map_err(|e| error(path, e.to_string()))
+}
+
+pub fn write_lines(path: &str, lines: &[Line]) -> Result<(), DataError> {
+    fs::write(path, encode_lines(lines)).map_err(|e| error(path, e.to_string()))
+}
+
+pub fn read_points(path: &str) -> Result<Vec<Point>, DataError> {
+    let bytes = fs::read(path).map_err(|e| error(path, e.to_string()))?;
+    decode_points(&bytes, path)
+}
+
+pub fn read_segments(path: &str) -> Result<Vec<Line>, DataError> {
+    let bytes = fs::read(path).map_err(|e| error(path, e.to_string()))?;
+    decode_segments(&bytes, path)
+}
+
+// `path` is only used for error messages.
+pub fn decode_points(bytes: &[u8], path: &str) -> Result<Vec<Point>, DataError> {
+    let payload = payload(bytes, Kind::Points, path)?;
+    Ok(payload
+        .chunks_exact(16)
+        .map(|record| Point {
+            x: f64_at(record, 0),
+            y: f64_at(record, 1),
+        })
+        .collect())
+}
+
+pub fn decode_segments(bytes: &[u8], path: &str) -> Result<Vec<Line>, DataError> {
+    let payload = payload(bytes, Kind::Segments, path)?;
+    Ok(payload
+        .chunks_exact(32)
+        .map(|record| {
+            Line::new(
+                Point {
+                    x: f64_at(record, 0),
+                    y: f64_at(record, 1),
+                },
+                Point {
+                    x: f64_at(record, 2),
+                    y: f64_at(record, 3),
+                },
+            )
+        })
+        .collect())
+}
+
+// The records of a lines file without copying them. Fails on big-endian machines, if the
+// payload is not aligned for `Line` (which `LineFile` takes care of) and if a normal or
+// distance differs from the one `Line::new` computes for the endpoints.
+pub fn decode_lines<'a>(bytes: &'a [u8], path: &str) -> Result<&'a [Line], DataError> {
+    let payload = payload(bytes, Kind::Lines, path)?;
+    if cfg!(target_endian = "big") {
+        return Err(error(
+            path,
+            "lines can only be read on little-endian machines",
+        ));
+    }
+    if !payload.as_ptr().cast::<Line>().is_aligned() {
+        return Err(error(path, "the records are not aligned"));
+    }
+    // SAFETY: `Line` consists of seven f64 in the order of the record (repr(C)), every bit
+    // pattern is a valid f64, and size and alignment of the payload were checked.
+    let lines = unsafe {
+        slice::from_raw_parts(
+            payload.as_ptr().cast::<Line>(),
+            payload.len() / size_of::<Line>(),
+        )
+    };
+    let bits = |line: &Line| [line.n.x, line.n.y, line.a].map(f64::to_bits);
+    if let Some(i) = lines
+        .iter()
+        .position(|line| bits(line) != bits(&Line::new(line.p1, line.p2)))
+    {
+        return Err(error(
+            path,
+            format!("line {} does not match its endpoints", i),
+        ));
+    }
+    Ok(lines)
+}
+
+// A segments or lines file as `Line` slice. Lines files are read into a buffer that is aligned
+// for `Line` and used as they are, segments are converted.
+pub struct LineFile {
+    storage: Storage,
+}
+
+enum Storage {
+    // f64, so that the records are aligned
+    Lines { buffer: Vec<f64>, count: usize },
+    Segments(Vec<Line>),
+}
+
+impl LineFile {
+    pub fn read(path: &str) -> Result<Self, DataError> {
+        let mut file = File::open(path).map_err(|e| error(path, e.to_string()))?;
+        let size = file
+            .metadata()
+            .map_err(|e| error(path, e.to_string()))?
+            .len() as usize;
+        let mut buffer = vec![0.0; size.div_ceil(size_of::<f64>())];
+        // SAFETY: the bytes lie within the buffer and every bit pattern is a valid f64.
+        let bytes = unsafe { slice::from_raw_parts_mut(buffer.as_mut_ptr().cast::<u8>(), size) };
+        file.read_exact(bytes)
+            .map_err(|e| error(path, e.to_string()))?;
+        let storage = match decode_header(bytes, path)?.kind {
+            Kind::Segments => Storage::Segments(decode_segments(bytes, path)?),
+            _ => {
+                let count = decode_lines(bytes, path)?.len();
+                Storage::Lines { buffer, count }
+            }
+        };
+        Ok(Self { storage })
+    }
+
+    pub fn lines(&self) -> &[Line] {
+        match &self.storage {
+            // SAFETY: `read` checked the records with `decode_lines`, the payload starts at
+            // HEADER_SIZE, which is a multiple of the alignment of `Line`
+            Storage::Lines { buffer, count } => unsafe {
+                slice::from_raw_parts(
+                    buffer[HEADER_SIZE / size_of::<f64>()..]
+                        .as_ptr()
+                        .cast::<Line>(),
+                    *count,
+                )
+            },
+            Storage::Segments(lines) => lines,
+        }
+    }
+}
+
+pub fn decode_header(bytes: &[u8], path: &str) -> Result<Header, DataError> {
+    if bytes.len() < HEADER_SIZE {
+        return Err(error(path, "file too short for a header"));
+    }
+    if bytes[..8] != MAGIC {
+        return Err(error(path, "not a binary point or segment file"));
+    }
+    let version = u32::from_le_bytes(bytes[8..12].try_into().unwrap());
+    if version != VERSION {
+        return Err(error(path, format!("unsupported version {}", version)));
+    }
+    let kind = match u32::from_le_bytes(bytes[12..16].try_into().unwrap()) {
+        1 => Kind::Points,
+        2 => Kind::Segments,
+        3 => Kind::Lines,
+        other => return Err(error(path, format!("unknown kind {}", other))),
+    };
+    let header = &bytes[16..HEADER_SIZE];
+    Ok(Header {
+        version,
+        kind,
+        count: u64::from_le_bytes(header[..8].try_into().unwrap()),
+        min: Point {
+            x: f64_at(&header[8..], 0),
+            y: f64_at(&header[8..], 1),
+        },
+        max: Point {
+            x: f64_at(&header[8..], 2),
+            y: f64_at(&header[8..], 3),
+        },
+    })
+}
+
+fn encode(
+    kind: Kind,
+    count: usize,
+    points: impl Iterator<Item = Point>,
+    values: impl Iterator<Item = f64>,
+) -> Vec<u8> {
+    let mut min = Point {
+        x: f64::INFINITY,
+        y: f64::INFINITY,
+    };
+    let mut max = Point {
+        x: f64::NEG_INFINITY,
+        y: f64::NEG_INFINITY,
+    };
+    for point in points {
+        min.x = min.x.min(point.x);
+        max.x = max.x.max(point.x);
+        min.y = min.y.min(point.y);
+        max.y = max.y.max(point.y);
+    }
+
+    let mut bytes =
+        Vec::with_capacity(HEADER_SIZE + count * kind.values_per_record() * size_of::<f64>());
+    bytes.extend(MAGIC);
+    bytes.extend(VERSION.to_le_bytes());
+    bytes.extend((kind as u32).to_le_bytes());
+    bytes.extend((count as u64).to_le_bytes());
+    for value in [min.x, min.y, max.x, max.y] {
+        bytes.extend(value.to_le_bytes());
+    }
+    for value in values {
+        bytes.extend(value.to_le_bytes());
+    }
+    bytes
+}
+
+fn payload<'a>(bytes: &'a [u8], kind: Kind, path: &str) -> Result<&'a [u8], DataError> {
+    let header = decode_header(bytes, path)?;
+    if header.kind != kind {
+        return Err(error(
+            path,
+            format!("expected {:?}, found {:?}", kind, header.kind),
+        ));
+    }
+    let size = (header.count as usize)
+        .checked_mul(kind.values_per_record() * size_of::<f64>())
+        .filter(|&size| size == bytes.len() - HEADER_SIZE)
+        .ok_or_else(|| {
+            error(
+                path,
+                format!(
+                    "{} records announced, but the payload has {} bytes",
+                    header.count,
+                    bytes.len() - HEADER_SIZE
+                ),
+            )
+        })?;
+    Ok(&bytes[HEADER_SIZE..HEADER_SIZE + size])
+}
+
+fn f64_at(record: &[u8], index: usize) -> f64 {
+    f64::from_le_bytes(record[index * 8..index * 8 + 8].try_into().unwrap())
+}
+
+fn error(path: &str, reason: impl Into<String>) -> DataError {
+    DataError {
+        path: path.to_string(),
+        line: 0,
+        column: 0,
+        reason: reason.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let lines = vec![
+            Line::new(Point { x: 1.5, y: -2.0 }, Point { x: 3.0, y: 4.0 }),
+            Line::new(Point { x: -7.0, y: 0.1 }, Point { x: 0.0, y: 1e300 }),
+        ];
+        let bytes = encode_segments(&lines);
+        assert_eq!(bytes.len(), HEADER_SIZE + 2 * 32);
+        assert_eq!(decode_segments(&bytes, "test.bin").unwrap(), lines);
+
+        let header = decode_header(&bytes, "test.bin").unwrap();
+        assert_eq!(header.kind, Kind::Segments);
+        assert_eq!(header.count, 2);
+        assert_eq!(header.min, Point { x: -7.0, y: -2.0 });
+        assert_eq!(header.max, Point { x: 3.0, y: 1e300 });
+
+        let points = vec![Point { x: 1.0, y: 2.0 }];
+        let bytes = encode_points(&points);
+        assert_eq!(decode_points(&bytes, "test.bin").unwrap(), points);
+        assert!(decode_segments(&bytes, "test.bin").is_err());
+        assert!(decode_points(&bytes[..bytes.len() - 1], "test.bin").is_err());
+    }
+
+    #[test]
+    fn lines_are_borrowed() {
+        let lines = vec![
+            Line::new(Point { x: 1.5, y: -2.0 }, Point { x: 3.0, y: 4.0 }),
+            Line::new(Point { x: -7.0, y: 0.1 }, Point { x: 0.0, y: 2.0 }),
+        ];
+        let path = std::env::temp_dir().join("cg_ss_25_lines.bin");
+        let path = path.to_str().unwrap();
+        write_lines(path, &lines).unwrap();
+        let file = LineFile::read(path).unwrap();
+        assert_eq!(file.lines(), &lines[..]);
+        let mut bytes = fs::read(path).unwrap();
+        let header = decode_header(&bytes, path).unwrap();
+        assert_eq!((header.kind, header.count), (Kind::Lines, 2));
+        assert_eq!(header.min, Point { x: -7.0, y: -2.0 });
+
+        // a normal that does not belong to the endpoints
+        bytes[HEADER_SIZE + 7 * 8 + 4 * 8] ^= 1;
+        fs::write(path, &bytes).unwrap();
+        let error = LineFile::read(path).err().unwrap();
+        assert_eq!(error.reason, "line 1 does not match its endpoints");
+
+        // segments are converted
+        write_segments(path, &lines).unwrap();
+        assert_eq!(LineFile::read(path).unwrap().lines(), &lines[..]);
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,520 @@
+use crate::lib::{
+    ccw::ccw,
+    common::SNAP_DISTANCE,
+    point::Point,
+    polygon::{MultiPolygon, Polygon, close_ring, is_ccw, ring_contains, ring_signed_area},
+    triangulation::normalized_ring,
+};
+use std::{collections::HashMap, f64::consts::PI};
+
+// Union of arbitrary polygons (with holes).
+//
+// Every edge is split at all points where it meets an edge of another polygon. A piece is part of
+// the union boundary if it lies outside of all other polygons; pieces that lie on the boundary of
+// another polygon are kept once if both run in the same direction and dropped if they run in
+// opposite directions (shared inner boundary). The pieces are then linked to rings again.
+pub fn union(polygons: &[Polygon]) -> MultiPolygon {
+    // exteriors counter-clockwise, holes clockwise => the interior is always on the left
+    let oriented: Vec<Vec<Vec<Point>>> = polygons
+        .iter()
+        .map(|polygon| {
+            let mut rings = vec![normalized_ring(&polygon.exterior)];
+            for hole in &polygon.holes {
+                let mut hole = normalized_ring(hole);
+                hole.reverse();
+                rings.push(hole);
+            }
+            rings
+        })
+        .collect();
+
+    let edges: Vec<(usize, Point, Point)> = oriented
+        .iter()
+        .enumerate()
+        .flat_map(|(i, rings)| {
+            rings.iter().flat_map(move |ring| {
+                (0..ring.len()).map(move |k| (i, ring[k], ring[(k + 1) % ring.len()]))
+            })
+        })
+        .collect();
+
+    let boxes = polygon_boxes(&oriented);
+    let mut snapper = Snapper::default();
+    let mut fragments: Vec<(usize, usize)> = Vec::new();
+
+    let mut parameters = vec![vec![0.0, 1.0]; edges.len()];
+    for (i, j) in overlapping_edges(&edges) {
+        let ((owner, a, b), (other, c, d)) = (edges[i], edges[j]);
+        if owner != other {
+            split_parameters(&a, &b, &c, &d, &mut parameters[i]);
+            split_parameters(&c, &d, &a, &b, &mut parameters[j]);
+        }
+    }
+
+    for (&(owner, a, b), parameters) in edges.iter().zip(&mut parameters) {
+        parameters.sort_by(|s, t| s.partial_cmp(t).unwrap());
+
+        for pair in parameters.windows(2) {
+            let start = snapper.snap(point_at(&a, &b, pair[0]));
+            let end = snapper.snap(point_at(&a, &b, pair[1]));
+            if start == end {
+                continue;
+            }
+            let (p, q) = (snapper.points[start], snapper.points[end]);
+            if keep_fragment(&oriented, polygons, &boxes, owner, &p, &q) {
+                fragments.push((start, end));
+            }
+        }
+    }
+
+    let rings = link_fragments(&snapper.points, &fragments)
+        .into_iter()
+        .map(|ring| remove_collinear(&ring))
+        .collect();
+    rings_to_polygons(rings)
+}
+
+// Groups rings into polygons: counter-clockwise rings are exteriors, clockwise rings are holes
+// of the smallest exterior that contains them.
+pub fn rings_to_polygons(rings: Vec<Vec<Point>>) -> MultiPolygon {
+    let (exteriors, holes): (Vec<_>, Vec<_>) = rings
+        .into_iter()
+        .filter(|ring| ring_signed_area(ring).abs() > SNAP_DISTANCE)
+        .partition(|ring| ring_signed_area(ring) > 0.0);
+
+    let mut result: MultiPolygon = exteriors.into_iter().map(Polygon::new).collect();
+    for hole in holes {
+        let probe = point_inside_hole(&hole);
+        let container = result
+            .iter_mut()
+            .filter(|polygon| ring_contains(&polygon.exterior, &probe))
+            .min_by(|a, b| {
+                ring_signed_area(&a.exterior)
+                    .partial_cmp(&ring_signed_area(&b.exterior))
+                    .unwrap()
+            });
+        if let Some(polygon) = container {
+            polygon.holes.push(Polygon::new(hole).exterior);
+        }
+    }
+    result
+}
+
+// Groups rings with arbitrary orientation by the even-odd rule: rings inside an even number of
+// other rings are exteriors, the others are holes (e.g. the subpaths of an SVG path).
+pub fn even_odd_polygons(rings: Vec<Vec<Point>>) -> MultiPolygon {
+    let rings: Vec<Vec<Point>> = rings.into_iter().filter(|ring| !ring.is_empty()).collect();
+    let depths: Vec<usize> = rings
+        .iter()
+        .enumerate()
+        .map(|(i, ring)| {
+            rings
+                .iter()
+                .enumerate()
+                .filter(|&(j, other)| i != j && ring_contains(other, &ring[0]))
+                .count()
+        })
+        .collect();
+    let oriented = rings
+        .into_iter()
+        .zip(depths)
+        .map(|(mut ring, depth)| {
+            if is_ccw(&ring) != (depth % 2 == 0) {
+                ring.reverse();
+            }
+            ring
+        })
+        .collect();
+    rings_to_polygons(oriented)
+}
+
+#[derive(Default)]
+pub struct Snapper {
+    pub points: Vec<Point>,
+    grid: HashMap<(i64, i64), Vec<usize>>,
+}
+
+impl Snapper {
+    // Returns the index of an already known point within SNAP_DISTANCE, or adds the point.
+    pub fn snap(&mut self, point: Point) -> usize {
+        let cell = Self::cell(&point);
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                if let Some(candidates) = self.grid.get(&(cell.0 + dx, cell.1 + dy)) {
+                    if let Some(&i) = candidates
+                        .iter()
+                        .find(|&&i| self.points[i].distance(&point) <= SNAP_DISTANCE)
+                    {
+                        return i;
+                    }
+                }
+            }
+        }
+        self.points.push(point);
+        self.grid
+            .entry(cell)
+            .or_default()
+            .push(self.points.len() - 1);
+        self.points.len() - 1
+    }
+
+    fn cell(point: &Point) -> (i64, i64) {
+        (
+            (point.x / SNAP_DISTANCE).floor() as i64,
+            (point.y / SNAP_DISTANCE).floor() as i64,
+        )
+    }
+}
+
+fn point_at(a: &Point, b: &Point, t: f64) -> Point {
+    if t == 0.0 {
+        *a
+    } else if t == 1.0 {
+        *b
+    } else {
+        Point {
+            x: a.x + (b.x - a.x) * t,
+            y: a.y + (b.y - a.y) * t,
+        }
+    }
+}
+
+// Pairs of edges with overlapping bounding boxes. Sorted by their left end, an edge is only
+// compared with the following ones starting before its right end.
+fn overlapping_edges(edges: &[(usize, Point, Point)]) -> Vec<(usize, usize)> {
+    let left = |i: usize| edges[i].1.x.min(edges[i].2.x);
+    let mut order: Vec<usize> = (0..edges.len()).collect();
+    order.sort_by(|&i, &j| left(i).total_cmp(&left(j)));
+    let mut pairs = Vec::new();
+    for (k, &i) in order.iter().enumerate() {
+        let (_, a, b) = edges[i];
+        let right = a.x.max(b.x) + SNAP_DISTANCE;
+        for &j in order[k + 1..].iter().take_while(|&&j| left(j) <= right) {
+            let (_, c, d) = edges[j];
+            if boxes_overlap(&a, &b, &c, &d) {
+                pairs.push((i, j));
+            }
+        }
+    }
+    pairs
+}
+
+fn boxes_overlap(a: &Point, b: &Point, c: &Point, d: &Point) -> bool {
+    a.x.min(b.x) <= c.x.max(d.x) + SNAP_DISTANCE
+        && c.x.min(d.x) <= a.x.max(b.x) + SNAP_DISTANCE
+        && a.y.min(b.y) <= c.y.max(d.y) + SNAP_DISTANCE
+        && c.y.min(d.y) <= a.y.max(b.y) + SNAP_DISTANCE
+}
+
+fn cross(u: &Point, v: &Point) -> f64 {
+    u.x * v.y - u.y * v.x
+}
+
+// Adds the parameters t in (0, 1) at which a->b has to be split because of c->d.
+fn split_parameters(a: &Point, b: &Point, c: &Point, d: &Point, parameters: &mut Vec<f64>) {
+    let r = *b - *a;
+    let s = *d - *c;
+    let length = r.x.hypot(r.y);
+    if length == 0.0 {
+        return;
+    }
+    let tolerance = SNAP_DISTANCE / length;
+    let denominator = cross(&r, &s);
+    let mut add = |t: f64| {
+        if t > tolerance && t < 1.0 - tolerance {
+            parameters.push(t);
+        }
+    };
+
+    if denominator.abs() <= SNAP_DISTANCE * length * s.x.hypot(s.y) {
+        // parallel: split at the endpoints of c->d that lie on a->b
+        for p in [c, d] {
+            if p.distance_to_segment(a, b) <= SNAP_DISTANCE {
+                add(((p.x - a.x) * r.x + (p.y - a.y) * r.y) / (length * length));
+            }
+        }
+        return;
+    }
+    let ac = *c - *a;
+    let t = cross(&ac, &s) / denominator;
+    let u = cross(&ac, &r) / denominator;
+    let u_tolerance = SNAP_DISTANCE / s.x.hypot(s.y).max(SNAP_DISTANCE);
+    if (-u_tolerance..=1.0 + u_tolerance).contains(&u) {
+        add(t);
+    }
+}
+
+// Bounding boxes of the exteriors grown by SNAP_DISTANCE, (lower left, upper right, polygon)
+// sorted by their left end.
+fn polygon_boxes(oriented: &[Vec<Vec<Point>>]) -> Vec<(Point, Point, usize)> {
+    let mut boxes: Vec<(Point, Point, usize)> = oriented
+        .iter()
+        .enumerate()
+        .map(|(i, rings)| {
+            let (min, max) = rings[0].iter().fold(
+                (
+                    Point {
+                        x: f64::INFINITY,
+                        y: f64::INFINITY,
+                    },
+                    Point {
+                        x: f64::NEG_INFINITY,
+                        y: f64::NEG_INFINITY,
+                    },
+                ),
+                |(min, max), p| {
+                    (
+                        Point {
+                            x: min.x.min(p.x),
+                            y: min.y.min(p.y),
+                        },
+                        Point {
+                            x: max.x.max(p.x),
+                            y: max.y.max(p.y),
+                        },
+                    )
+                },
+            );
+            let grow = Point {
+                x: SNAP_DISTANCE,
+                y: SNAP_DISTANCE,
+            };
+            (min - grow, max + grow, i)
+        })
+        .collect();
+    boxes.sort_by(|a, b| a.0.x.total_cmp(&b.0.x));
+    boxes
+}
+
+// Only the polygons whose boxes contain the middle of the fragment can contain it or have it on
+// their boundary.
+fn keep_fragment(
+    oriented: &[Vec<Vec<Point>>],
+    polygons: &[Polygon],
+    boxes: &[(Point, Point, usize)],
+    owner: usize,
+    p: &Point,
+    q: &Point,
+) -> bool {
+    let middle = Point {
+        x: (p.x + q.x) / 2.0,
+        y: (p.y + q.y) / 2.0,
+    };
+    let direction = *q - *p;
+    let end = boxes.partition_point(|(min, _, _)| min.x <= middle.x);
+    for &(min, max, other) in &boxes[..end] {
+        if other == owner || middle.x > max.x || middle.y < min.y || middle.y > max.y {
+            continue;
+        }
+        let rings = &oriented[other];
+        let boundary_edge = rings.iter().find_map(|ring| {
+            (0..ring.len())
+                .map(|k| (ring[k], ring[(k + 1) % ring.len()]))
+                .find(|(c, d)| middle.distance_to_segment(c, d) <= SNAP_DISTANCE)
+        });
+        match boundary_edge {
+            Some((c, d)) => {
+                let same_direction = direction.x * (d.x - c.x) + direction.y * (d.y - c.y) > 0.0;
+                // keep shared boundaries only once, drop boundaries between two interiors
+                if !same_direction || other < owner {
+                    return false;
+                }
+            }
+            None => {
+                if polygons[other].contains(&middle) {
+                    return false;
+                }
+            }
+        }
+    }
+    true
+}
+
+// Links directed fragments to closed rings, keeping the interior on the left. At vertices with
+// several outgoing fragments the first one clockwise from the incoming fragment is taken, so
+// rings touching in a single vertex are returned separately.
+fn link_fragments(points: &[Point], fragments: &[(usize, usize)]) -> Vec<Vec<Point>> {
+    let mut outgoing: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (i, &(start, _)) in fragments.iter().enumerate() {
+        outgoing.entry(start).or_default().push(i);
+    }
+    let mut used = vec![false; fragments.len()];
+    let mut rings = Vec::new();
+
+    for first in 0..fragments.len() {
+        if used[first] {
+            continue;
+        }
+        let origin = fragments[first].0;
+        let mut ring = vec![points[origin]];
+        let mut current = first;
+        loop {
+            used[current] = true;
+            let (from, to) = fragments[current];
+            if to == origin {
+                ring.push(points[origin]);
+                rings.push(ring);
+                break;
+            }
+            ring.push(points[to]);
+            let back = (points[from].y - points[to].y).atan2(points[from].x - points[to].x);
+            let next = outgoing.get(&to).and_then(|candidates| {
+                candidates
+                    .iter()
+                    .filter(|&&c| !used[c])
+                    .min_by(|&&c1, &&c2| {
+                        let turn = |c: usize| {
+                            let target = points[fragments[c].1];
+                            let angle = (target.y - points[to].y).atan2(target.x - points[to].x);
+                            let turn = (back - angle).rem_euclid(2.0 * PI);
+                            if turn == 0.0 { 2.0 * PI } else { turn }
+                        };
+                        turn(c1).partial_cmp(&turn(c2)).unwrap()
+                    })
+                    .copied()
+            });
+            match next {
+                Some(next) => current = next,
+                // open chain, caused by numerical problems
+                None => break,
+            }
+        }
+    }
+    rings
+}
+
+// Drops the vertices that only exist because an edge was split.
+fn remove_collinear(ring: &[Point]) -> Vec<Point> {
+    let mut points: Vec<Point> = ring[..ring.len() - 1].to_vec();
+    let mut i = 0;
+    while points.len() > 3 && i < points.len() {
+        let n = points.len();
+        if ccw(&points[(i + n - 1) % n], &points[i], &points[(i + 1) % n]) == 0 {
+            points.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+    close_ring(points)
+}
+
+fn point_inside_hole(hole: &[Point]) -> Point {
+    // the hole is clockwise, so its inside lies to the right of the longest edge
+    let (a, b) = hole
+        .windows(2)
+        .map(|pair| (pair[0], pair[1]))
+        .max_by(|(a, b), (c, d)| a.distance(b).partial_cmp(&c.distance(d)).unwrap())
+        .unwrap();
+    let length = a.distance(&b);
+    let offset = (SNAP_DISTANCE * 1000.0).min(length / 1000.0);
+    Point {
+        x: (a.x + b.x) / 2.0 + (b.y - a.y) / length * offset,
+        y: (a.y + b.y) / 2.0 - (b.x - a.x) / length * offset,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(x1: f64, y1: f64, x2: f64, y2: f64) -> Vec<Point> {
+        [(x1, y1), (x2, y1), (x2, y2), (x1, y2)]
+            .iter()
+            .map(|&(x, y)| Point { x, y })
+            .collect()
+    }
+
+    fn area(polygons: &MultiPolygon) -> f64 {
+        polygons.iter().map(Polygon::area).sum()
+    }
+
+    #[test]
+    fn overlapping_squares() {
+        let result = union(&[
+            Polygon::new(square(0.0, 0.0, 2.0, 2.0)),
+            Polygon::new(square(1.0, 1.0, 3.0, 3.0)),
+        ]);
+        assert_eq!(result.len(), 1);
+        assert!((area(&result) - 7.0).abs() < 1e-9);
+        assert_eq!(result[0].vertex_count(), 8);
+        assert!(result[0].holes.is_empty());
+    }
+
+    #[test]
+    fn shared_edge_disappears() {
+        let result = union(&[
+            Polygon::new(square(0.0, 0.0, 1.0, 1.0)),
+            Polygon::new(square(1.0, 0.0, 2.0, 1.0)),
+            // shares only a part of the upper edge of both
+            Polygon::new(square(0.5, 1.0, 1.5, 2.0)),
+        ]);
+        assert_eq!(result.len(), 1);
+        assert!((area(&result) - 3.0).abs() < 1e-9);
+        assert_eq!(result[0].vertex_count(), 8);
+    }
+
+    #[test]
+    fn squares_touching_in_a_vertex() {
+        let result = union(&[
+            Polygon::new(square(0.0, 0.0, 1.0, 1.0)),
+            Polygon::new(square(1.0, 1.0, 2.0, 2.0)),
+        ]);
+        assert_eq!(result.len(), 2);
+        assert!((area(&result) - 2.0).abs() < 1e-9);
+        assert!(result.iter().all(|polygon| polygon.vertex_count() == 4));
+    }
+
+    #[test]
+    fn polygon_with_hole() {
+        let frame =
+            Polygon::with_holes(square(0.0, 0.0, 6.0, 6.0), vec![square(2.0, 2.0, 4.0, 4.0)]);
+
+        // covers a quarter of the hole
+        let result = union(&[frame.clone(), Polygon::new(square(1.0, 1.0, 3.0, 3.0))]);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].holes.len(), 1);
+        assert!((area(&result) - 33.0).abs() < 1e-9);
+
+        // fills the hole
+        let result = union(&[frame, Polygon::new(square(2.0, 2.0, 4.0, 4.0))]);
+        assert_eq!(result.len(), 1);
+        assert!(result[0].holes.is_empty());
+        assert!((area(&result) - 36.0).abs() < 1e-9);
+        assert_eq!(result[0].vertex_count(), 4);
+    }
+
+    #[test]
+    fn grid_of_squares() {
+        let squares: Vec<Polygon> = (0..100)
+            .map(|i| {
+                let (x, y) = ((i % 10) as f64, (i / 10) as f64);
+                Polygon::new(square(x, y, x + 1.0, y + 1.0))
+            })
+            .collect();
+        let result = union(&squares);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].vertex_count(), 4);
+        assert!((area(&result) - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn square_in_the_bounding_box_of_another_polygon() {
+        let l_shape = [
+            (0.0, 0.0),
+            (4.0, 0.0),
+            (4.0, 1.0),
+            (1.0, 1.0),
+            (1.0, 4.0),
+            (0.0, 4.0),
+        ]
+        .iter()
+        .map(|&(x, y)| Point { x, y })
+        .collect();
+        let result = union(&[
+            Polygon::new(l_shape),
+            Polygon::new(square(2.0, 2.0, 3.0, 3.0)),
+        ]);
+        assert_eq!(result.len(), 2);
+        assert!((area(&result) - 8.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,232 @@
+use crate::lib::line::Line;
+use std::thread;
+
+// Faster versions of the O(n²) brute force of p01. Both return the same pairs as
+// `verify::brute_force`: (i, j) with i < j for which `Line::crosses` holds, sorted.
+
+// All pairs, the rows of the pair matrix distributed over all cores.
+pub fn parallel_brute_force(lines: &[Line]) -> Vec<(usize, usize)> {
+    let mut pairs = parallel(lines.len(), |i| {
+        (i + 1..lines.len())
+            .filter(|&j| lines[i].crosses(&lines[j]))
+            .map(|j| (i, j))
+            .collect()
+    });
+    pairs.sort_unstable();
+    pairs
+}
+
+// Only pairs whose bounding boxes share a cell of a uniform grid are checked.
+pub fn grid_brute_force(lines: &[Line]) -> Vec<(usize, usize)> {
+    let grid = UniformGrid::new(lines);
+    let mut pairs = parallel(grid.cells.len(), |cell| grid.pairs_in_cell(lines, cell));
+    pairs.sort_unstable();
+    pairs
+}
+
+// For fallible input like a `SegmentReader`, which is read completely first.
+pub fn try_parallel_brute_force<E>(
+    lines: impl IntoIterator<Item = Result<Line, E>>,
+) -> Result<Vec<(usize, usize)>, E> {
+    Ok(parallel_brute_force(
+        &lines.into_iter().collect::<Result<Vec<_>, E>>()?,
+    ))
+}
+
+pub fn try_grid_brute_force<E>(
+    lines: impl IntoIterator<Item = Result<Line, E>>,
+) -> Result<Vec<(usize, usize)>, E> {
+    Ok(grid_brute_force(
+        &lines.into_iter().collect::<Result<Vec<_>, E>>()?,
+    ))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoundingBox {
+    pub min_x: f64,
+    pub min_y: f64,
+    pub max_x: f64,
+    pub max_y: f64,
+}
+
+impl BoundingBox {
+    pub fn of(line: &Line) -> Self {
+        Self {
+            min_x: line.p1.x.min(line.p2.x),
+            min_y: line.p1.y.min(line.p2.y),
+            max_x: line.p1.x.max(line.p2.x),
+            max_y: line.p1.y.max(line.p2.y),
+        }
+    }
+
+    // touching boxes overlap, like touching segments cross
+    pub fn overlaps(&self, other: &BoundingBox) -> bool {
+        self.min_x <= other.max_x
+            && other.min_x <= self.max_x
+            && self.min_y <= other.max_y
+            && other.min_y <= self.max_y
+    }
+}
+
+// Every segment is in all cells its bounding box overlaps.
+struct UniformGrid {
+    boxes: Vec<BoundingBox>,
+    origin: (f64, f64),
+    cell_size: f64,
+    columns: usize,
+    rows: usize,
+    // indices of the segments per cell, row by row
+    cells: Vec<Vec<usize>>,
+}
+
+impl UniformGrid {
+    // About one segment per cell, but cells are not smaller than the average segment.
+    fn new(lines: &[Line]) -> Self {
+        let boxes: Vec<BoundingBox> = lines.iter().map(BoundingBox::of).collect();
+        let bounds = boxes.iter().fold(
+            BoundingBox {
+                min_x: f64::INFINITY,
+                min_y: f64::INFINITY,
+                max_x: f64::NEG_INFINITY,
+                max_y: f64::NEG_INFINITY,
+            },
+            |a, b| BoundingBox {
+                min_x: a.min_x.min(b.min_x),
+                min_y: a.min_y.min(b.min_y),
+                max_x: a.max_x.max(b.max_x),
+                max_y: a.max_y.max(b.max_y),
+            },
+        );
+        let extent = (bounds.max_x - bounds.min_x).max(bounds.max_y - bounds.min_y);
+        let average = boxes
+            .iter()
+            .map(|b| (b.max_x - b.min_x).max(b.max_y - b.min_y))
+            .sum::<f64>()
+            / boxes.len().max(1) as f64;
+        let cell_size = (extent / (lines.len() as f64).sqrt().max(1.0)).max(average);
+        Self::with_cell_size(boxes, (bounds.min_x, bounds.min_y), extent, cell_size)
+    }
+
+    fn with_cell_size(
+        boxes: Vec<BoundingBox>,
+        origin: (f64, f64),
+        extent: f64,
+        cell_size: f64,
+    ) -> Self {
+        // a single cell for empty input or if all segments are one point
+        let cell_size = if cell_size > 0.0 { cell_size } else { 1.0 };
+        let count = if extent > 0.0 {
+            (extent / cell_size) as usize + 1
+        } else {
+            1
+        };
+        let mut grid = Self {
+            boxes,
+            origin,
+            cell_size,
+            columns: count,
+            rows: count,
+            cells: vec![Vec::new(); count * count],
+        };
+        for id in 0..grid.boxes.len() {
+            let bounds = grid.boxes[id];
+            let (first_column, first_row) = grid.cell_of(bounds.min_x, bounds.min_y);
+            let (last_column, last_row) = grid.cell_of(bounds.max_x, bounds.max_y);
+            for row in first_row..=last_row {
+                for column in first_column..=last_column {
+                    grid.cells[row * grid.columns + column].push(id);
+                }
+            }
+        }
+        grid
+    }
+
+    fn cell_of(&self, x: f64, y: f64) -> (usize, usize) {
+        let column = ((x - self.origin.0) / self.cell_size) as usize;
+        let row = ((y - self.origin.1) / self.cell_size) as usize;
+        (column.min(self.columns - 1), row.min(self.rows - 1))
+    }
+
+    // A pair is checked only in the cell of the lower left corner of the overlap of both
+    // boxes, so every pair is checked once.
+    fn pairs_in_cell(&self, lines: &[Line], cell: usize) -> Vec<(usize, usize)> {
+        let ids = &self.cells[cell];
+        let mut pairs = Vec::new();
+        for (k, &i) in ids.iter().enumerate() {
+            for &j in &ids[k + 1..] {
+                let (a, b) = (&self.boxes[i], &self.boxes[j]);
+                if !a.overlaps(b) {
+                    continue;
+                }
+                let (column, row) = self.cell_of(a.min_x.max(b.min_x), a.min_y.max(b.min_y));
+                if row * self.columns + column == cell && lines[i].crosses(&lines[j]) {
+                    pairs.push((i.min(j), i.max(j)));
+                }
+            }
+        }
+        pairs
+    }
+}
+
+// Runs `job` for 0..jobs on all cores, job k on thread k % threads.
+fn parallel<T: Send>(jobs: usize, job: impl Fn(usize) -> Vec<T> + Sync) -> Vec<T> {
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|first| {
+                let job = &job;
+                scope.spawn(move || {
+                    (first..jobs)
+                        .step_by(threads)
+                        .flat_map(job)
+                        .collect::<Vec<T>>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lib::{
+        generator::SegmentGenerator, point::Point, segment_reader::SegmentReader,
+        verify::brute_force,
+    };
+
+    #[test]
+    fn same_pairs_as_brute_force() {
+        for seed in 0..3 {
+            let mut generator =
+                SegmentGenerator::new(seed, Point { x: 0.0, y: 0.0 }, Point { x: 100.0, y: 100.0 });
+            for lines in [
+                generator.uniform(500, 10.0),
+                generator.uniform(200, 100.0),
+                generator.vertical(200, 20.0),
+                generator.grid_snapped(400, 15.0, 5.0),
+                generator.concurrent(30, 60.0),
+                generator.collinear(50, 20.0),
+                Vec::new(),
+            ] {
+                let expected = brute_force(&lines);
+                assert_eq!(parallel_brute_force(&lines), expected);
+                assert_eq!(grid_brute_force(&lines), expected);
+            }
+        }
+        let point = Line::new(Point { x: 1.0, y: 1.0 }, Point { x: 1.0, y: 1.0 });
+        assert_eq!(grid_brute_force(&[point.clone(), point]), vec![(0, 1)]);
+    }
+
+    #[test]
+    fn fallible_input() {
+        let lines = || SegmentReader::new("0 0 2 2\n0 2 2 0\n5 5 6 6\n".as_bytes(), "test");
+        assert_eq!(try_grid_brute_force(lines()).unwrap(), vec![(0, 1)]);
+        assert_eq!(try_parallel_brute_force(lines()).unwrap(), vec![(0, 1)]);
+        let broken = SegmentReader::new("0 0 2 2\n0 2\n".as_bytes(), "test");
+        assert_eq!(try_grid_brute_force(broken).unwrap_err().line, 2);
+    }
+}
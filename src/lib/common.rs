@@ -1 +1,8 @@
 pub const EPSILON: f64 = 1E-12;
+
+// Computed points (e.g. intersections) closer than this are treated as the same point
+pub const SNAP_DISTANCE: f64 = 1E-9;
+
+// Event points of the sweep line closer than this are the same point. Crossing points of almost
+// parallel segments are a lot less accurate than SNAP_DISTANCE.
+pub const SWEEP_TOLERANCE: f64 = 1E-7;
@@ -1,65 +1,198 @@
 use crate::lib::{line::Line, point::Point};
-use std::fs::read_to_string;
+use std::{error::Error, fmt, fmt::Write, fs, fs::read_to_string};
 
-pub fn read_polygon_from_file(path: &str) -> Vec<Point> {
-    let rows = read_file_rows(path);
+// Where and why reading an input file failed. Line and column are 1-based, both are 0 if the
+// error is not about a specific position (e.g. a missing file).
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataError {
+    pub path: String,
+    pub line: usize,
+    pub column: usize,
+    pub reason: String,
+}
+
+impl fmt::Display for DataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line == 0 {
+            write!(f, "{}: {}", self.path, self.reason)
+        } else {
+            write!(
+                f,
+                "{}:{}:{}: {}",
+                self.path, self.line, self.column, self.reason
+            )
+        }
+    }
+}
+
+impl Error for DataError {}
 
+pub fn read_polygon_from_file(path: &str) -> Result<Vec<Point>, DataError> {
+    parse_polygon(&read_file(path)?, path)
+}
+
+pub fn read_lines_from_file(path: &str) -> Result<Vec<Line>, DataError> {
+    parse_lines(&read_file(path)?, path)
+}
+
+// One point "x y" per row, the ring is closed if it is not already.
+pub fn parse_polygon(text: &str, path: &str) -> Result<Vec<Point>, DataError> {
     let mut points: Vec<Point> = Vec::new();
-    for row in rows {
-        points.push(string_to_point(row))
+    for (index, row) in text.lines().enumerate() {
+        if let Some([x, y]) = parse_row(row, index + 1, path)? {
+            points.push(Point { x, y });
+        }
     }
-    if points.first().unwrap() != points.last().unwrap() {
-        points.push(*points.first().unwrap());
+    match (points.first(), points.last()) {
+        (Some(first), Some(last)) if first != last => points.push(*first),
+        (None, _) => {
+            return Err(DataError {
+                path: path.to_string(),
+                line: 0,
+                column: 0,
+                reason: "no points".to_string(),
+            });
+        }
+        _ => {}
     }
+    Ok(points)
+}
 
-    points
+// One segment "x1 y1 x2 y2" per row.
+pub fn parse_lines(text: &str, path: &str) -> Result<Vec<Line>, DataError> {
+    segments(text, path).collect()
 }
 
-pub fn read_lines_from_file(path: &str) -> Vec<Line> {
-    let rows = read_file_rows(path);
+// Parses the segments lazily, without copying any row.
+pub fn segments<'a>(
+    text: &'a str,
+    path: &'a str,
+) -> impl Iterator<Item = Result<Line, DataError>> + 'a {
+    text.lines()
+        .enumerate()
+        .filter_map(|(index, row)| parse_row(row, index + 1, path).transpose())
+        .map(|numbers| numbers.map(numbers_to_line))
+}
 
-    let mut lines: Vec<Line> = Vec::new();
-    for row in rows {
-        lines.push(string_to_line(row))
+// Writes segments in the same format `read_lines_from_file` reads.
+pub fn write_lines_to_file(path: &str, lines: &[Line]) -> Result<(), DataError> {
+    let mut text = String::with_capacity(lines.len() * 40);
+    for line in lines {
+        writeln!(
+            text,
+            "{} {} {} {}",
+            line.p1.x, line.p1.y, line.p2.x, line.p2.y
+        )
+        .unwrap();
     }
-    lines
+    fs::write(path, text).map_err(|e| DataError {
+        path: path.to_string(),
+        line: 0,
+        column: 0,
+        reason: e.to_string(),
+    })
+}
+
+pub(crate) fn numbers_to_line([x1, y1, x2, y2]: [f64; 4]) -> Line {
+    Line::new(Point { x: x1, y: y1 }, Point { x: x2, y: y2 })
 }
 
-fn string_to_point(string: String) -> Point {
-    let numbers: Vec<f64> = string
-        .split(&" ")
-        .filter(|s| !s.is_empty())
-        .map(|word| word.parse::<f64>().unwrap())
-        .collect::<Vec<f64>>();
-    Point {
-        x: numbers[0],
-        y: numbers[1],
+// Exactly N finite numbers separated by any whitespace. Everything after '#' is a comment,
+// rows without numbers are skipped (None).
+pub(crate) fn parse_row<const N: usize>(
+    row: &str,
+    line: usize,
+    path: &str,
+) -> Result<Option<[f64; N]>, DataError> {
+    let error = |column: usize, reason: String| DataError {
+        path: path.to_string(),
+        line,
+        column,
+        reason,
+    };
+    let content = row.split('#').next().unwrap_or("");
+
+    let mut numbers = [0.0; N];
+    let mut count = 0;
+    let mut words = content.char_indices().peekable();
+    while let Some(&(start, c)) = words.peek() {
+        if c.is_whitespace() {
+            words.next();
+            continue;
+        }
+        let mut end = start;
+        while let Some(&(i, c)) = words.peek() {
+            if c.is_whitespace() {
+                break;
+            }
+            end = i + c.len_utf8();
+            words.next();
+        }
+        let word = &content[start..end];
+        let column = content[..start].chars().count() + 1;
+        if count == N {
+            return Err(error(column, format!("expected {} numbers, found more", N)));
+        }
+        let number = word
+            .parse::<f64>()
+            .map_err(|_| error(column, format!("'{}' is not a number", word)))?;
+        if !number.is_finite() {
+            return Err(error(column, format!("'{}' is not a finite number", word)));
+        }
+        numbers[count] = number;
+        count += 1;
+    }
+
+    match count {
+        0 => Ok(None),
+        _ if count == N => Ok(Some(numbers)),
+        _ => Err(error(
+            content.trim_end().chars().count() + 1,
+            format!("expected {} numbers, found {}", N, count),
+        )),
     }
 }
 
-fn string_to_line(string: String) -> Line {
-    let numbers: Vec<f64> = string
-        .split(&" ")
-        .map(|word| word.parse::<f64>().unwrap())
-        .collect::<Vec<f64>>();
-    Line::new(
-        Point {
-            x: numbers[0],
-            y: numbers[1],
-        },
-        Point {
-            x: numbers[2],
-            y: numbers[3],
-        },
-    )
+fn read_file(path: &str) -> Result<String, DataError> {
+    read_to_string(path).map_err(|e| DataError {
+        path: path.to_string(),
+        line: 0,
+        column: 0,
+        reason: e.to_string(),
+    })
 }
 
-fn read_file_rows(filename: &str) -> Vec<String> {
-    let mut result = Vec::new();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whitespace_comments_and_notation() {
+        let text = "# segments\n1 2\t3  4 \n\n  5e-1 -2.5E2 7 8 # last one\n";
+        let lines = parse_lines(text, "test.dat").unwrap();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1].p1, Point { x: 0.5, y: -250.0 });
+        assert_eq!(lines[1].p2, Point { x: 7.0, y: 8.0 });
 
-    for row in read_to_string(filename).unwrap().lines() {
-        result.push(row.to_string())
+        let polygon = parse_polygon("0 0\n1 0\n1 1\n", "polygon.txt").unwrap();
+        assert_eq!(polygon.len(), 4);
+        assert_eq!(polygon.first(), polygon.last());
     }
 
-    result
+    #[test]
+    fn errors_with_position() {
+        let error = parse_lines("1 2 3 4\n1 2 x 4\n", "test.dat").unwrap_err();
+        assert_eq!((error.line, error.column), (2, 5));
+        assert_eq!(error.to_string(), "test.dat:2:5: 'x' is not a number");
+
+        let error = parse_lines("1 2 3\n", "test.dat").unwrap_err();
+        assert_eq!((error.line, error.column), (1, 6));
+        assert!(parse_lines("1 2 NaN 4\n", "test.dat").is_err());
+        assert!(parse_lines("1 2 inf 4\n", "test.dat").is_err());
+        assert!(parse_polygon("# nothing\n", "polygon.txt").is_err());
+        assert_eq!(
+            read_lines_from_file("does/not/exist.dat").unwrap_err().line,
+            0
+        );
+    }
 }
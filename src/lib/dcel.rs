@@ -0,0 +1,474 @@
+use crate::lib::{
+    common::SWEEP_TOLERANCE,
+    line::Line,
+    point::Point,
+    polygon::{ring_contains, ring_signed_area},
+    sweep::{Intersection, intersections},
+};
+use std::collections::HashMap;
+
+// Doubly connected edge list of the arrangement of a set of segments. Segments are split at
+// every intersection, so edges only meet at vertices. Edge e consists of the half-edges 2e and
+// 2e + 1, every half-edge has its face on the left, i.e. outer boundaries run counter-clockwise
+// and holes clockwise.
+#[derive(Clone, Debug, Default)]
+pub struct Dcel {
+    pub vertices: Vec<Vertex>,
+    pub half_edges: Vec<HalfEdge>,
+    pub faces: Vec<Face>,
+    // the input segments every edge lies on, more than one where segments overlap
+    pub edge_segments: Vec<Vec<usize>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Vertex {
+    pub point: Point,
+    // one of the half-edges starting here
+    pub outgoing: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HalfEdge {
+    pub origin: usize,
+    pub twin: usize,
+    pub next: usize,
+    pub prev: usize,
+    pub face: usize,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Face {
+    // a half-edge of the outer boundary, None for the unbounded face
+    pub outer: Option<usize>,
+    // a half-edge of every hole, i.e. of every component inside the face
+    pub inner: Vec<usize>,
+}
+
+// The unbounded face always has index 0.
+pub const OUTER_FACE: usize = 0;
+
+impl Dcel {
+    // Points (segments of length zero) add no vertices.
+    pub fn from_segments(lines: &[Line]) -> Self {
+        Self::from_intersections(lines, &intersections(lines.iter().cloned()))
+    }
+
+    // `intersections` has to contain every pair of crossing segments, e.g. the result of a sweep.
+    pub fn from_intersections(lines: &[Line], intersections: &[Intersection]) -> Self {
+        // the points where every segment is split
+        let mut splits: Vec<Vec<Point>> = lines.iter().map(|line| vec![line.p1, line.p2]).collect();
+        for intersection in intersections {
+            let (a, b) = intersection.segments;
+            splits[a].push(intersection.point);
+            splits[b].push(intersection.point);
+            // touching and overlapping segments are only reported at one of their common points
+            for (on, other) in [(a, b), (b, a)] {
+                for p in [lines[other].p1, lines[other].p2] {
+                    if p.distance_to_segment(&lines[on].p1, &lines[on].p2) <= SWEEP_TOLERANCE {
+                        splits[on].push(p);
+                    }
+                }
+            }
+        }
+
+        let mut dcel = Dcel::default();
+        let mut vertex_index = VertexIndex::default();
+        let mut edge_index: HashMap<(usize, usize), usize> = HashMap::new();
+        for (id, line) in lines.iter().enumerate() {
+            let direction = line.p2 - line.p1;
+            let length = direction.x * direction.x + direction.y * direction.y;
+            if length == 0.0 {
+                continue;
+            }
+            let position = |p: &Point| {
+                ((p.x - line.p1.x) * direction.x + (p.y - line.p1.y) * direction.y) / length
+            };
+            let points = &mut splits[id];
+            points.sort_by(|p, q| position(p).total_cmp(&position(q)));
+            let mut previous = None;
+            for p in points.iter() {
+                let vertex = vertex_index.find_or_insert(&mut dcel.vertices, *p);
+                if let Some(previous) = previous.filter(|&previous| previous != vertex) {
+                    let key = (vertex.min(previous), vertex.max(previous));
+                    let edge = *edge_index.entry(key).or_insert_with(|| {
+                        dcel.edge_segments.push(Vec::new());
+                        dcel.add_edge(previous, vertex)
+                    });
+                    if !dcel.edge_segments[edge].contains(&id) {
+                        dcel.edge_segments[edge].push(id);
+                    }
+                }
+                previous = Some(vertex);
+            }
+        }
+        dcel.link_around_vertices();
+        dcel.create_faces();
+        dcel
+    }
+
+    fn add_edge(&mut self, from: usize, to: usize) -> usize {
+        let edge = self.half_edges.len() / 2;
+        for (origin, twin) in [(from, 2 * edge + 1), (to, 2 * edge)] {
+            self.half_edges.push(HalfEdge {
+                origin,
+                twin,
+                next: twin,
+                prev: twin,
+                face: OUTER_FACE,
+            });
+            self.vertices[origin].outgoing = twin ^ 1;
+        }
+        edge
+    }
+
+    // Around every vertex the half-edge after an incoming one is the next outgoing one in
+    // clockwise order.
+    fn link_around_vertices(&mut self) {
+        let mut outgoing: Vec<Vec<usize>> = vec![Vec::new(); self.vertices.len()];
+        for (h, half_edge) in self.half_edges.iter().enumerate() {
+            outgoing[half_edge.origin].push(h);
+        }
+        for around in &mut outgoing {
+            around.sort_by(|&a, &b| self.angle(a).total_cmp(&self.angle(b)));
+            for (i, &h) in around.iter().enumerate() {
+                let clockwise = around[(i + around.len() - 1) % around.len()];
+                let incoming = self.half_edges[h].twin;
+                self.half_edges[incoming].next = clockwise;
+                self.half_edges[clockwise].prev = incoming;
+            }
+        }
+    }
+
+    // Counter-clockwise cycles are outer boundaries of bounded faces. Every other cycle is the
+    // outer boundary of a connected component and a hole in the smallest face of another
+    // component around it.
+    fn create_faces(&mut self) {
+        self.faces = vec![Face::default()];
+        let component = self.components();
+        // the first half-edge of the cycle of every half-edge
+        let mut cycle_of = vec![usize::MAX; self.half_edges.len()];
+        let mut holes = Vec::new();
+        // rings of the bounded faces, for the search of the face around a hole
+        let mut rings = Vec::new();
+        for start in 0..self.half_edges.len() {
+            if cycle_of[start] != usize::MAX {
+                continue;
+            }
+            let cycle: Vec<usize> = self.cycle(start).collect();
+            cycle.iter().for_each(|&h| cycle_of[h] = start);
+            // Shoelace formula without the edges walked in both directions. Their terms would
+            // cancel, but rounding can leave a positive area for a tree.
+            let area: f64 = cycle
+                .iter()
+                .filter(|&&h| cycle_of[self.half_edges[h].twin] != start)
+                .map(|&h| {
+                    let from = self.vertices[self.half_edges[h].origin].point;
+                    let to = self.vertices[self.destination(h)].point;
+                    from.x * to.y - to.x * from.y
+                })
+                .sum();
+            if area > 0.0 {
+                let ring = self.ring(start);
+                let face = self.faces.len();
+                self.faces.push(Face {
+                    outer: Some(start),
+                    inner: Vec::new(),
+                });
+                cycle.iter().for_each(|&h| self.half_edges[h].face = face);
+                rings.push((face, component[self.half_edges[start].origin], ring));
+            } else {
+                holes.push((start, cycle));
+            }
+        }
+        for (start, cycle) in holes {
+            let own = component[self.half_edges[start].origin];
+            let point = self.vertices[self.half_edges[start].origin].point;
+            let face = rings
+                .iter()
+                .filter(|(_, other, ring)| *other != own && ring_contains(ring, &point))
+                .min_by(|a, b| ring_signed_area(&a.2).total_cmp(&ring_signed_area(&b.2)))
+                .map_or(OUTER_FACE, |(face, _, _)| *face);
+            self.faces[face].inner.push(start);
+            cycle.iter().for_each(|&h| self.half_edges[h].face = face);
+        }
+    }
+
+    // connected component of every vertex
+    fn components(&self) -> Vec<usize> {
+        let mut component = vec![usize::MAX; self.vertices.len()];
+        for start in 0..self.vertices.len() {
+            if component[start] != usize::MAX {
+                continue;
+            }
+            component[start] = start;
+            let mut stack = vec![start];
+            while let Some(vertex) = stack.pop() {
+                for h in self.outgoing(vertex) {
+                    let neighbour = self.destination(h);
+                    if component[neighbour] == usize::MAX {
+                        component[neighbour] = start;
+                        stack.push(neighbour);
+                    }
+                }
+            }
+        }
+        component
+    }
+
+    fn angle(&self, h: usize) -> f64 {
+        let from = self.vertices[self.half_edges[h].origin].point;
+        let to = self.vertices[self.destination(h)].point;
+        (to.y - from.y).atan2(to.x - from.x)
+    }
+
+    pub fn destination(&self, h: usize) -> usize {
+        self.half_edges[self.half_edges[h].twin].origin
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.half_edges.len() / 2
+    }
+
+    // The input segments the edge of half-edge `h` lies on.
+    pub fn segments(&self, h: usize) -> &[usize] {
+        &self.edge_segments[h / 2]
+    }
+
+    // The half-edges of the boundary cycle through `start`, following `next`.
+    pub fn cycle(&self, start: usize) -> impl Iterator<Item = usize> + '_ {
+        let mut current = Some(start);
+        std::iter::from_fn(move || {
+            let h = current?;
+            let next = self.half_edges[h].next;
+            current = (next != start).then_some(next);
+            Some(h)
+        })
+    }
+
+    // The half-edges starting at `vertex`, counter-clockwise.
+    pub fn outgoing(&self, vertex: usize) -> impl Iterator<Item = usize> + '_ {
+        let start = self.vertices[vertex].outgoing;
+        let mut current = Some(start);
+        std::iter::from_fn(move || {
+            let h = current?;
+            let next = self.half_edges[self.half_edges[h].prev].twin;
+            current = (next != start).then_some(next);
+            Some(h)
+        })
+    }
+
+    // The closed ring of points along the cycle through `start`.
+    pub fn ring(&self, start: usize) -> Vec<Point> {
+        let mut ring: Vec<Point> = self
+            .cycle(start)
+            .map(|h| self.vertices[self.half_edges[h].origin].point)
+            .collect();
+        ring.push(ring[0]);
+        ring
+    }
+
+    // The outer ring (none for the unbounded face) followed by the holes.
+    pub fn face_rings(&self, face: usize) -> Vec<Vec<Point>> {
+        let face = &self.faces[face];
+        face.outer
+            .iter()
+            .chain(&face.inner)
+            .map(|&h| self.ring(h))
+            .collect()
+    }
+
+    // Infinite for the unbounded face.
+    pub fn area(&self, face: usize) -> f64 {
+        let face = &self.faces[face];
+        let Some(outer) = face.outer else {
+            return f64::INFINITY;
+        };
+        // holes run clockwise, their areas are negative
+        ring_signed_area(&self.ring(outer))
+            + face
+                .inner
+                .iter()
+                .map(|&h| ring_signed_area(&self.ring(h)))
+                .sum::<f64>()
+    }
+
+    // A point strictly inside a bounded face, e.g. to look the face up in another map. It lies
+    // on the vertical line through the widest gap between vertex x-coordinates, in the middle of
+    // the longest piece of that line inside the face.
+    pub fn interior_point(&self, face: usize) -> Option<Point> {
+        let rings = self.faces[face].outer.map(|_| self.face_rings(face))?;
+        let mut xs: Vec<f64> = rings.iter().flatten().map(|p| p.x).collect();
+        xs.sort_by(f64::total_cmp);
+        let x = xs
+            .windows(2)
+            .max_by(|a, b| (a[1] - a[0]).total_cmp(&(b[1] - b[0])))
+            .map(|w| (w[0] + w[1]) / 2.0)?;
+        // no vertex lies on the line, so every edge is crossed at most once
+        let mut ys: Vec<f64> = rings
+            .iter()
+            .flat_map(|ring| ring.windows(2))
+            .filter(|w| (w[0].x < x) != (w[1].x < x))
+            .map(|w| w[0].y + (x - w[0].x) / (w[1].x - w[0].x) * (w[1].y - w[0].y))
+            .collect();
+        ys.sort_by(f64::total_cmp);
+        // even-odd, between every second pair of crossings the line is inside
+        ys.chunks_exact(2)
+            .max_by(|a, b| (a[1] - a[0]).total_cmp(&(b[1] - b[0])))
+            .map(|pair| Point {
+                x,
+                y: (pair[0] + pair[1]) / 2.0,
+            })
+    }
+}
+
+// Finds vertices closer than SWEEP_TOLERANCE in a hash grid, crossing points of the same
+// segments can be computed slightly differently.
+#[derive(Default)]
+struct VertexIndex {
+    cells: HashMap<(i64, i64), Vec<usize>>,
+}
+
+impl VertexIndex {
+    fn find_or_insert(&mut self, vertices: &mut Vec<Vertex>, point: Point) -> usize {
+        let (x, y) = Self::cell(&point);
+        for cell in [x - 1, x, x + 1]
+            .into_iter()
+            .flat_map(|x| [(x, y - 1), (x, y), (x, y + 1)])
+        {
+            if let Some(found) = self.cells.get(&cell).and_then(|ids| {
+                ids.iter().copied().find(|&id| {
+                    let other = vertices[id].point;
+                    (other.x - point.x).abs() <= SWEEP_TOLERANCE
+                        && (other.y - point.y).abs() <= SWEEP_TOLERANCE
+                })
+            }) {
+                return found;
+            }
+        }
+        vertices.push(Vertex {
+            point,
+            outgoing: usize::MAX,
+        });
+        self.cells
+            .entry((x, y))
+            .or_default()
+            .push(vertices.len() - 1);
+        vertices.len() - 1
+    }
+
+    fn cell(point: &Point) -> (i64, i64) {
+        (
+            (point.x / SWEEP_TOLERANCE).floor() as i64,
+            (point.y / SWEEP_TOLERANCE).floor() as i64,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lib::generator::SegmentGenerator;
+
+    fn line(x1: f64, y1: f64, x2: f64, y2: f64) -> Line {
+        Line::new(Point { x: x1, y: y1 }, Point { x: x2, y: y2 })
+    }
+
+    fn component_count(dcel: &Dcel) -> usize {
+        let mut roots: Vec<usize> = dcel.components();
+        roots.sort_unstable();
+        roots.dedup();
+        roots.len()
+    }
+
+    // V - E + F = 1 + C
+    fn assert_euler(dcel: &Dcel) {
+        assert_eq!(
+            dcel.vertices.len() + dcel.faces.len(),
+            dcel.edge_count() + 1 + component_count(dcel)
+        );
+    }
+
+    #[test]
+    fn square_with_diagonal_and_islands() {
+        let dcel = Dcel::from_segments(&[
+            line(0.0, 0.0, 4.0, 0.0),
+            line(4.0, 0.0, 4.0, 4.0),
+            line(4.0, 4.0, 0.0, 4.0),
+            line(0.0, 4.0, 0.0, 0.0),
+            line(0.0, 0.0, 4.0, 4.0),
+            // a triangle in the lower right half
+            line(3.0, 1.0, 3.5, 1.0),
+            line(3.5, 1.0, 3.5, 2.0),
+            line(3.5, 2.0, 3.0, 1.0),
+            // an isolated segment outside
+            line(10.0, 0.0, 11.0, 0.0),
+        ]);
+        assert_eq!(
+            (dcel.vertices.len(), dcel.edge_count(), dcel.faces.len()),
+            (9, 9, 4)
+        );
+        assert_euler(&dcel);
+        let mut areas: Vec<f64> = (1..dcel.faces.len()).map(|f| dcel.area(f)).collect();
+        areas.sort_by(f64::total_cmp);
+        assert_eq!(areas, vec![0.25, 7.75, 8.0]);
+        assert_eq!(dcel.area(OUTER_FACE), f64::INFINITY);
+        // the square and the isolated segment are holes of the unbounded face
+        assert_eq!(dcel.faces[OUTER_FACE].inner.len(), 2);
+        let lower = (1..dcel.faces.len())
+            .find(|&f| dcel.area(f) == 7.75)
+            .unwrap();
+        assert_eq!(dcel.faces[lower].inner.len(), 1);
+        assert_eq!(dcel.face_rings(lower).len(), 2);
+        for h in 0..dcel.half_edges.len() {
+            let half_edge = dcel.half_edges[h];
+            assert_eq!(dcel.half_edges[half_edge.next].prev, h);
+            assert_eq!(dcel.half_edges[half_edge.twin].twin, h);
+            assert_eq!(dcel.half_edges[half_edge.next].face, half_edge.face);
+        }
+    }
+
+    #[test]
+    fn splits_crossing_and_overlapping_segments() {
+        let dcel = Dcel::from_segments(&[
+            line(0.0, 0.0, 2.0, 2.0),
+            line(0.0, 2.0, 2.0, 0.0),
+            line(1.0, 1.0, 3.0, 3.0),
+            line(2.0, 0.0, 2.0, 2.0),
+        ]);
+        // vertices (0,0) (1,1) (2,2) (3,3) (0,2) (2,0)
+        assert_eq!((dcel.vertices.len(), dcel.edge_count()), (6, 6));
+        let center = dcel
+            .vertices
+            .iter()
+            .position(|v| v.point == Point { x: 1.0, y: 1.0 })
+            .unwrap();
+        assert_eq!(dcel.outgoing(center).count(), 4);
+        // the overlap of the first and the third segment
+        let overlap = (0..dcel.edge_segments.len())
+            .find(|&e| dcel.edge_segments[e].len() == 2)
+            .unwrap();
+        assert_eq!(dcel.edge_segments[overlap], vec![0, 2]);
+        assert_eq!(dcel.faces.len(), 2);
+        assert_eq!(dcel.area(1), 1.0);
+        assert_euler(&dcel);
+    }
+
+    #[test]
+    fn random_arrangements() {
+        for seed in 0..3 {
+            let mut generator =
+                SegmentGenerator::new(seed, Point { x: 0.0, y: 0.0 }, Point { x: 100.0, y: 100.0 });
+            for lines in [
+                generator.uniform(300, 30.0),
+                generator.grid_snapped(300, 20.0, 5.0),
+                generator.concurrent(20, 60.0),
+            ] {
+                let dcel = Dcel::from_segments(&lines);
+                assert_euler(&dcel);
+                for face in 1..dcel.faces.len() {
+                    assert!(dcel.area(face) > 0.0);
+                }
+            }
+        }
+    }
+}
@@ -1,7 +1,5 @@
-use crate::lib::line::Line;
-
 // Derivative provides 'Derive' makros that can ignore parameters.
-// => Events should be comparable only by their x-value, not by the referenced 'Line'
+// => Events should be comparable only by their key, not by the point and segments
 use derivative::Derivative;
 
 // Helper float class that has a total order for sorting
@@ -12,11 +10,24 @@ use ordered_float::NotNan;
 use crate::lib::point::Point;
 use std::{cmp::Reverse, collections::BinaryHeap, fmt};
 
-#[derive(Debug, Clone, PartialEq)]
+// Segments are referenced by their index in the input of the sweep.
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum EventType {
-    Start,
-    End,
-    Intersection(Point, Line, Line),
+    Start(usize),
+    End(usize),
+    // the segment below and the segment above the intersection point before it
+    Intersection(usize, usize),
+}
+
+impl EventType {
+    // At the same point segments end first, then they cross, then new ones start.
+    fn priority(&self) -> u8 {
+        match self {
+            EventType::End(_) => 0,
+            EventType::Intersection(_, _) => 1,
+            EventType::Start(_) => 2,
+        }
+    }
 }
 
 pub type EventHeap = BinaryHeap<Event>;
@@ -24,27 +35,64 @@ pub type EventHeap = BinaryHeap<Event>;
 #[derive(Derivative)]
 #[derivative(Eq, PartialEq, Ord, PartialOrd)]
 pub struct Event {
-    // Events are sortable by their x value, with the smallest x value being the first element.
-    pub x: Reverse<NotNan<f64>>,
+    // Events are sortable by (x, y, type), with the smallest key being the first element.
+    pub key: Reverse<(NotNan<f64>, NotNan<f64>, u8)>,
     #[derivative(PartialEq = "ignore", Ord = "ignore", PartialOrd = "ignore")]
-    pub line: Line,
+    pub point: Point,
     #[derivative(PartialEq = "ignore", Ord = "ignore", PartialOrd = "ignore")]
     pub event_type: EventType,
 }
 
 impl fmt::Debug for Event {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Event ({})", self.x.0)
+        write!(
+            f,
+            "Event ({}, {}, {:?})",
+            self.point.x, self.point.y, self.event_type
+        )
     }
 }
 
 impl Event {
     // Helper for constructing an event withouth having to deal with NotNan / Reverse classes
-    pub fn new(x: f64, line: Line, event_type: EventType) -> Self {
+    pub fn new(point: Point, event_type: EventType) -> Self {
         Self {
-            x: Reverse(NotNan::new(x).unwrap()),
-            line,
+            key: Reverse((
+                NotNan::new(point.x).unwrap(),
+                NotNan::new(point.y).unwrap(),
+                event_type.priority(),
+            )),
+            point,
             event_type,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lexicographic_order() {
+        let p = |x, y| Point { x, y };
+        let mut heap = EventHeap::new();
+        heap.push(Event::new(p(1.0, 2.0), EventType::Start(0)));
+        heap.push(Event::new(p(1.0, 2.0), EventType::End(1)));
+        heap.push(Event::new(p(1.0, 1.0), EventType::Start(2)));
+        heap.push(Event::new(p(1.0, 2.0), EventType::Intersection(3, 4)));
+        heap.push(Event::new(p(0.0, 5.0), EventType::End(5)));
+        let order: Vec<EventType> = std::iter::from_fn(|| heap.pop())
+            .map(|event| event.event_type)
+            .collect();
+        assert_eq!(
+            order,
+            vec![
+                EventType::End(5),
+                EventType::Start(2),
+                EventType::End(1),
+                EventType::Intersection(3, 4),
+                EventType::Start(0),
+            ]
+        );
+    }
+}
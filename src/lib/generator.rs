@@ -0,0 +1,223 @@
+use crate::lib::{line::Line, point::Point};
+use std::f64::consts::PI;
+
+// SplitMix64: small, fast and good enough for test data. The same seed always gives the same
+// sequence on every platform.
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // uniform in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    // uniform in [low, high)
+    pub fn range(&mut self, low: f64, high: f64) -> f64 {
+        low + (high - low) * self.next_f64()
+    }
+
+    // uniform in 0..n
+    pub fn index(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    // standard normal distribution (Box-Muller)
+    pub fn normal(&mut self) -> f64 {
+        let u = 1.0 - self.next_f64();
+        let v = self.next_f64();
+        (-2.0 * u.ln()).sqrt() * (2.0 * PI * v).cos()
+    }
+}
+
+// Random segment sets inside the box [min, max]. Every family takes a maximal segment length,
+// like the course files s_<count>_<maximal length>.dat.
+pub struct SegmentGenerator {
+    rng: Rng,
+    pub min: Point,
+    pub max: Point,
+}
+
+impl SegmentGenerator {
+    pub fn new(seed: u64, min: Point, max: Point) -> Self {
+        Self {
+            rng: Rng::new(seed),
+            min,
+            max,
+        }
+    }
+
+    pub fn points(&mut self, count: usize) -> Vec<Point> {
+        (0..count).map(|_| self.point()).collect()
+    }
+
+    // Start point uniform in the box, direction uniform, length uniform in [0, max_length].
+    pub fn uniform(&mut self, count: usize, max_length: f64) -> Vec<Line> {
+        (0..count)
+            .map(|_| {
+                let start = self.point();
+                let end = self.end_point(&start, max_length);
+                Line::new(start, end)
+            })
+            .collect()
+    }
+
+    // All segments on one line, so most of them overlap.
+    pub fn collinear(&mut self, count: usize, max_length: f64) -> Vec<Line> {
+        let (a, b) = (self.point(), self.point());
+        let direction = b - a;
+        let length = direction.x.hypot(direction.y).max(f64::MIN_POSITIVE);
+        (0..count)
+            .map(|_| {
+                let s = self.rng.next_f64();
+                let t = (s + self.rng.range(-1.0, 1.0) * max_length / length).clamp(0.0, 1.0);
+                Line::new(at(&a, &direction, s), at(&a, &direction, t))
+            })
+            .collect()
+    }
+
+    // Every segment starts at one of `hubs` common points.
+    pub fn shared_endpoints(&mut self, count: usize, max_length: f64, hubs: usize) -> Vec<Line> {
+        let hubs = self.points(hubs.max(1));
+        (0..count)
+            .map(|_| {
+                let start = hubs[self.rng.index(hubs.len())];
+                let end = self.end_point(&start, max_length);
+                Line::new(start, end)
+            })
+            .collect()
+    }
+
+    // Only vertical segments (p1.x == p2.x).
+    pub fn vertical(&mut self, count: usize, max_length: f64) -> Vec<Line> {
+        (0..count)
+            .map(|_| {
+                let start = self.point();
+                let y = (start.y + self.rng.range(-max_length, max_length))
+                    .clamp(self.min.y, self.max.y);
+                Line::new(start, Point { x: start.x, y })
+            })
+            .collect()
+    }
+
+    // Uniform segments with all endpoints snapped to a grid, which produces many touching,
+    // collinear, vertical and horizontal segments. Snapping moves an endpoint by up to
+    // spacing / sqrt(2), so segments that became longer than `max_length` or collapsed to a point
+    // are drawn again: every length is in [spacing, max_length], which needs
+    // spacing <= max_length. Endpoints can leave the box by less than spacing / 2.
+    pub fn grid_snapped(&mut self, count: usize, max_length: f64, spacing: f64) -> Vec<Line> {
+        assert!(
+            0.0 < spacing && spacing <= max_length,
+            "the spacing must be in (0, max_length]"
+        );
+        let snap = |p: Point| Point {
+            x: (p.x / spacing).round() * spacing,
+            y: (p.y / spacing).round() * spacing,
+        };
+        let mut lines = Vec::with_capacity(count);
+        while lines.len() < count {
+            let start = self.point();
+            let end = self.end_point(&start, max_length);
+            let line = Line::new(snap(start), snap(end));
+            if line.p1 != line.p2 && line.length() <= max_length {
+                lines.push(line);
+            }
+        }
+        lines
+    }
+
+    // Segments centered at one common point, i.e. all of them intersect there.
+    pub fn concurrent(&mut self, count: usize, max_length: f64) -> Vec<Line> {
+        let center = Point {
+            x: (self.min.x + self.max.x) / 2.0,
+            y: (self.min.y + self.max.y) / 2.0,
+        };
+        (0..count)
+            .map(|_| {
+                let angle = self.rng.range(0.0, PI);
+                let half = self.rng.range(0.0, max_length / 2.0);
+                let offset = Point {
+                    x: half * angle.cos(),
+                    y: half * angle.sin(),
+                };
+                Line::new(center - offset, center + offset)
+            })
+            .collect()
+    }
+
+    fn point(&mut self) -> Point {
+        Point {
+            x: self.rng.range(self.min.x, self.max.x),
+            y: self.rng.range(self.min.y, self.max.y),
+        }
+    }
+
+    fn end_point(&mut self, start: &Point, max_length: f64) -> Point {
+        let angle = self.rng.range(0.0, 2.0 * PI);
+        let length = self.rng.range(0.0, max_length);
+        Point {
+            x: (start.x + length * angle.cos()).clamp(self.min.x, self.max.x),
+            y: (start.y + length * angle.sin()).clamp(self.min.y, self.max.y),
+        }
+    }
+}
+
+fn at(a: &Point, direction: &Point, t: f64) -> Point {
+    Point {
+        x: a.x + direction.x * t,
+        y: a.y + direction.y * t,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generator(seed: u64) -> SegmentGenerator {
+        SegmentGenerator::new(seed, Point { x: 0.0, y: 0.0 }, Point { x: 100.0, y: 100.0 })
+    }
+
+    #[test]
+    fn reproducible_and_bounded() {
+        let lines = generator(7).uniform(1000, 10.0);
+        assert_eq!(lines, generator(7).uniform(1000, 10.0));
+        assert_ne!(lines, generator(8).uniform(1000, 10.0));
+        for line in &lines {
+            assert!(line.length() <= 10.0);
+            for p in [line.p1, line.p2] {
+                assert!((0.0..=100.0).contains(&p.x) && (0.0..=100.0).contains(&p.y));
+            }
+        }
+    }
+
+    #[test]
+    fn degenerate_families() {
+        let mut generator = generator(1);
+        assert!(generator.vertical(50, 5.0).iter().all(|l| l.p1.x == l.p2.x));
+        let spacing = 1.25;
+        let grid = generator.grid_snapped(500, 2.0, spacing);
+        assert_eq!(grid.len(), 500);
+        for line in &grid {
+            for c in [line.p1.x, line.p1.y, line.p2.x, line.p2.y] {
+                assert_eq!((c / spacing).fract(), 0.0, "{:?}", line);
+            }
+            assert!((spacing..=2.0).contains(&line.length()), "{:?}", line);
+        }
+        let star = generator.concurrent(20, 50.0);
+        assert!(star.iter().all(|a| star.iter().all(|b| a.crosses(b))));
+    }
+}
@@ -0,0 +1,329 @@
+use crate::lib::{
+    point::Point,
+    polygon::{Polygon, is_ccw},
+    wkt::Geometry,
+};
+use serde_json::{Map, Value, json};
+use std::{error::Error, fmt, fs};
+
+// GeoJSON (RFC 7946) features with the same geometry type as WKT. MultiPoint and
+// MultiLineString are read as geometry collections.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Feature {
+    // numeric ids are converted to strings
+    pub id: Option<String>,
+    // `"geometry": null` marks an unlocated feature (RFC 7946 section 3.2)
+    pub geometry: Option<Geometry>,
+    pub properties: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeoJsonError {
+    pub reason: String,
+}
+
+impl fmt::Display for GeoJsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.reason)
+    }
+}
+
+impl Error for GeoJsonError {}
+
+impl From<serde_json::Error> for GeoJsonError {
+    fn from(error: serde_json::Error) -> Self {
+        error_at("", error.to_string())
+    }
+}
+
+fn error_at(path: &str, reason: impl Into<String>) -> GeoJsonError {
+    let reason = reason.into();
+    GeoJsonError {
+        reason: if path.is_empty() {
+            reason
+        } else {
+            format!("{}: {}", path, reason)
+        },
+    }
+}
+
+impl Feature {
+    pub fn new(geometry: Geometry) -> Self {
+        Self {
+            id: None,
+            geometry: Some(geometry),
+            properties: Map::new(),
+        }
+    }
+
+    pub fn with_id(self, id: &str) -> Self {
+        Self {
+            id: Some(id.to_string()),
+            ..self
+        }
+    }
+
+    pub fn with_property(mut self, key: &str, value: impl Into<Value>) -> Self {
+        self.properties.insert(key.to_string(), value.into());
+        self
+    }
+}
+
+// Reads a FeatureCollection, a single Feature or a bare geometry.
+pub fn read_features(text: &str) -> Result<Vec<Feature>, GeoJsonError> {
+    let value: Value = serde_json::from_str(text)?;
+    match member_str(&value, "type", "")? {
+        "FeatureCollection" => value
+            .get("features")
+            .and_then(Value::as_array)
+            .ok_or_else(|| error_at("features", "missing or not an array"))?
+            .iter()
+            .enumerate()
+            .map(|(i, feature)| parse_feature(feature, &format!("features[{}]", i)))
+            .collect(),
+        "Feature" => Ok(vec![parse_feature(&value, "")?]),
+        _ => Ok(vec![Feature::new(parse_geometry(&value, "")?)]),
+    }
+}
+
+pub fn read_features_from_file(path: &str) -> Result<Vec<Feature>, GeoJsonError> {
+    let text = fs::read_to_string(path).map_err(|e| error_at(path, e.to_string()))?;
+    read_features(&text).map_err(|e| error_at(path, e.reason))
+}
+
+pub fn write_features(features: &[Feature]) -> String {
+    let features: Vec<Value> = features
+        .iter()
+        .map(|feature| {
+            let mut object = Map::new();
+            object.insert("type".to_string(), json!("Feature"));
+            if let Some(id) = &feature.id {
+                object.insert("id".to_string(), json!(id));
+            }
+            let geometry = feature
+                .geometry
+                .as_ref()
+                .map_or(Value::Null, geometry_to_json);
+            object.insert("geometry".to_string(), geometry);
+            object.insert(
+                "properties".to_string(),
+                Value::Object(feature.properties.clone()),
+            );
+            Value::Object(object)
+        })
+        .collect();
+    json!({ "type": "FeatureCollection", "features": features }).to_string()
+}
+
+pub fn write_features_to_file(path: &str, features: &[Feature]) -> std::io::Result<()> {
+    fs::write(path, write_features(features))
+}
+
+fn geometry_to_json(geometry: &Geometry) -> Value {
+    let point = |p: &Point| json!([p.x, p.y]);
+    let points = |points: &[Point]| Value::Array(points.iter().map(point).collect());
+    // RFC 7946: exteriors counter-clockwise, holes clockwise
+    let polygon = |polygon: &Polygon| {
+        let rings = polygon.rings().enumerate().map(|(i, ring)| {
+            if ring.is_empty() || is_ccw(ring) == (i == 0) {
+                points(ring)
+            } else {
+                let reversed: Vec<Point> = ring.iter().rev().copied().collect();
+                points(&reversed)
+            }
+        });
+        Value::Array(rings.filter(|ring| ring != &json!([])).collect())
+    };
+    match geometry {
+        Geometry::Point(p) => json!({ "type": "Point", "coordinates": point(p) }),
+        Geometry::LineString(line) => json!({ "type": "LineString", "coordinates": points(line) }),
+        Geometry::Polygon(p) => json!({ "type": "Polygon", "coordinates": polygon(p) }),
+        Geometry::MultiPolygon(polygons) => json!({
+            "type": "MultiPolygon",
+            "coordinates": polygons.iter().map(polygon).collect::<Vec<_>>(),
+        }),
+        Geometry::GeometryCollection(geometries) => json!({
+            "type": "GeometryCollection",
+            "geometries": geometries.iter().map(geometry_to_json).collect::<Vec<_>>(),
+        }),
+    }
+}
+
+// JSON path of a member for error messages, e.g. "features[3].geometry"
+fn child(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+fn member_str<'a>(value: &'a Value, key: &str, path: &str) -> Result<&'a str, GeoJsonError> {
+    value
+        .get(key)
+        .and_then(Value::as_str)
+        .ok_or_else(|| error_at(path, format!("missing or invalid \"{}\"", key)))
+}
+
+fn parse_feature(value: &Value, path: &str) -> Result<Feature, GeoJsonError> {
+    if member_str(value, "type", path)? != "Feature" {
+        return Err(error_at(path, "expected a Feature"));
+    }
+    let id = match value.get("id") {
+        Some(Value::String(id)) => Some(id.clone()),
+        Some(Value::Number(id)) => Some(id.to_string()),
+        None | Some(Value::Null) => None,
+        Some(_) => return Err(error_at(path, "\"id\" must be a string or a number")),
+    };
+    let properties = match value.get("properties") {
+        Some(Value::Object(properties)) => properties.clone(),
+        None | Some(Value::Null) => Map::new(),
+        Some(_) => return Err(error_at(path, "\"properties\" must be an object")),
+    };
+    let geometry = match value.get("geometry") {
+        Some(Value::Null) => None,
+        Some(geometry) => Some(parse_geometry(geometry, &child(path, "geometry"))?),
+        None => return Err(error_at(path, "missing \"geometry\"")),
+    };
+    Ok(Feature {
+        id,
+        geometry,
+        properties,
+    })
+}
+
+fn parse_geometry(value: &Value, path: &str) -> Result<Geometry, GeoJsonError> {
+    let kind = member_str(value, "type", path)?;
+    if kind == "GeometryCollection" {
+        let geometries = value
+            .get("geometries")
+            .and_then(Value::as_array)
+            .ok_or_else(|| error_at(path, "missing \"geometries\""))?;
+        return geometries
+            .iter()
+            .enumerate()
+            .map(|(i, g)| parse_geometry(g, &child(path, &format!("geometries[{}]", i))))
+            .collect::<Result<_, _>>()
+            .map(Geometry::GeometryCollection);
+    }
+
+    let path = child(path, "coordinates");
+    let coordinates = value
+        .get("coordinates")
+        .ok_or_else(|| error_at(&path, "missing"))?;
+    Ok(match kind {
+        "Point" => Geometry::Point(parse_position(coordinates, &path)?),
+        "LineString" => Geometry::LineString(parse_positions(coordinates, &path)?),
+        "Polygon" => Geometry::Polygon(parse_polygon(coordinates, &path)?),
+        "MultiPolygon" => Geometry::MultiPolygon(
+            parse_array(coordinates, &path)?
+                .iter()
+                .map(|polygon| parse_polygon(polygon, &path))
+                .collect::<Result<_, _>>()?,
+        ),
+        "MultiPoint" => Geometry::GeometryCollection(
+            parse_positions(coordinates, &path)?
+                .into_iter()
+                .map(Geometry::Point)
+                .collect(),
+        ),
+        "MultiLineString" => Geometry::GeometryCollection(
+            parse_array(coordinates, &path)?
+                .iter()
+                .map(|line| parse_positions(line, &path).map(Geometry::LineString))
+                .collect::<Result<_, _>>()?,
+        ),
+        other => {
+            return Err(error_at(
+                &path,
+                format!("unsupported geometry type {}", other),
+            ));
+        }
+    })
+}
+
+fn parse_array<'a>(value: &'a Value, path: &str) -> Result<&'a Vec<Value>, GeoJsonError> {
+    value
+        .as_array()
+        .ok_or_else(|| error_at(path, "expected an array"))
+}
+
+// [x, y] or [x, y, z], the altitude is dropped
+fn parse_position(value: &Value, path: &str) -> Result<Point, GeoJsonError> {
+    let position = parse_array(value, path)?;
+    match position.as_slice() {
+        [x, y] | [x, y, _] => match (x.as_f64(), y.as_f64()) {
+            (Some(x), Some(y)) => Ok(Point { x, y }),
+            _ => Err(error_at(path, "coordinates must be numbers")),
+        },
+        _ => Err(error_at(path, "a position needs two or three numbers")),
+    }
+}
+
+fn parse_positions(value: &Value, path: &str) -> Result<Vec<Point>, GeoJsonError> {
+    parse_array(value, path)?
+        .iter()
+        .map(|position| parse_position(position, path))
+        .collect()
+}
+
+fn parse_polygon(value: &Value, path: &str) -> Result<Polygon, GeoJsonError> {
+    let mut rings = parse_array(value, path)?
+        .iter()
+        .map(|ring| parse_positions(ring, path))
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter();
+    Ok(match rings.next() {
+        Some(exterior) => Polygon::with_holes(exterior, rings.collect()),
+        None => Polygon::new(Vec::new()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_and_write() {
+        let text = r#"{
+            "type": "FeatureCollection",
+            "features": [
+                {"type": "Feature", "id": "Bayern", "properties": {"area": 70550.2},
+                 "geometry": {"type": "Polygon", "coordinates": [[[0, 0], [0, 1], [1, 1], [1, 0], [0, 0]]]}},
+                {"type": "Feature", "id": 7, "properties": null,
+                 "geometry": {"type": "MultiPoint", "coordinates": [[1, 2, 3], [4, 5]]}}
+            ]
+        }"#;
+        let features = read_features(text).unwrap();
+        assert_eq!(features[0].id.as_deref(), Some("Bayern"));
+        assert_eq!(features[0].properties["area"], json!(70550.2));
+        let geometry = features[0].geometry.as_ref().unwrap();
+        assert_eq!(geometry.polygons()[0].area(), 1.0);
+        assert_eq!(features[1].id.as_deref(), Some("7"));
+
+        let written = write_features(&features);
+        let reread = read_features(&written).unwrap();
+        assert_eq!(reread[1], features[1]);
+        // the clockwise exterior is written counter-clockwise
+        let polygons = reread[0].geometry.as_ref().unwrap().polygons();
+        assert!(is_ccw(&polygons[0].exterior));
+
+        let error = read_features(r#"{"type": "Feature", "geometry": {"type": "Point"}}"#);
+        assert_eq!(error.unwrap_err().reason, "geometry.coordinates: missing");
+    }
+
+    #[test]
+    fn unlocated_feature() {
+        let text = r#"{"type": "Feature", "id": "Ausland", "geometry": null, "properties": {}}"#;
+        let features = read_features(text).unwrap();
+        assert_eq!(features[0].geometry, None);
+        assert_eq!(features[0].id.as_deref(), Some("Ausland"));
+
+        let written: Value = serde_json::from_str(&write_features(&features)).unwrap();
+        assert_eq!(written["features"][0]["geometry"], Value::Null);
+        assert_eq!(read_features(&written.to_string()).unwrap(), features);
+
+        let error = read_features(r#"{"type": "Feature", "properties": {}}"#).unwrap_err();
+        assert_eq!(error.reason, "missing \"geometry\"");
+    }
+}
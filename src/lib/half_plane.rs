@@ -0,0 +1,275 @@
+use crate::lib::{common::EPSILON, line::Line, point::Point, polygon::Polygon};
+use std::collections::VecDeque;
+
+// The closed half-plane to the left of the directed line through `point` along `direction`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HalfPlane {
+    pub point: Point,
+    pub direction: Point,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum HalfPlaneIntersection {
+    Empty,
+    Unbounded,
+    Bounded(Polygon),
+}
+
+impl HalfPlane {
+    pub fn new(point: Point, direction: Point) -> Self {
+        Self { point, direction }
+    }
+
+    // Left of the directed edge a -> b, i.e. the inside for counter-clockwise polygons.
+    pub fn through(a: Point, b: Point) -> Self {
+        Self::new(a, b - a)
+    }
+
+    // The side of `line` that contains the origin (n · p <= a), as used for the LP in p05.
+    pub fn from_line(line: &Line) -> Self {
+        Self::new(
+            Point {
+                x: line.n.x * line.a,
+                y: line.n.y * line.a,
+            },
+            Point {
+                x: -line.n.y,
+                y: line.n.x,
+            },
+        )
+    }
+
+    // > 0 inside, < 0 outside (scaled by the length of `direction`)
+    pub fn side(&self, p: &Point) -> f64 {
+        self.direction.x * (p.y - self.point.y) - self.direction.y * (p.x - self.point.x)
+    }
+
+    pub fn contains(&self, p: &Point) -> bool {
+        !self.is_outside(p)
+    }
+
+    fn is_outside(&self, p: &Point) -> bool {
+        self.side(p) < -EPSILON * self.direction.x.hypot(self.direction.y).max(1.0)
+    }
+
+    fn angle(&self) -> f64 {
+        self.direction.y.atan2(self.direction.x)
+    }
+
+    fn boundary_intersection(&self, other: &HalfPlane) -> Point {
+        let cross = self.direction.x * other.direction.y - self.direction.y * other.direction.x;
+        let t = (other.direction.x * (self.point.y - other.point.y)
+            - other.direction.y * (self.point.x - other.point.x))
+            / cross;
+        Point {
+            x: self.point.x + self.direction.x * t,
+            y: self.point.y + self.direction.y * t,
+        }
+    }
+}
+
+// Intersection of half-planes in O(n log n): sort by angle, then add the half-planes one by
+// one to a deque and drop the ones that became redundant at both ends.
+//
+// A large bounding box is added to make every intersection bounded. If the result touches that
+// box, the actual intersection is unbounded.
+pub fn intersect_half_planes(half_planes: &[HalfPlane]) -> HalfPlaneIntersection {
+    let extent = half_planes
+        .iter()
+        .map(|h| h.point.x.abs().max(h.point.y.abs()))
+        .fold(0.0, f64::max);
+    let bound = (extent + 1.0) * 1E6;
+    let corners = [
+        Point {
+            x: -bound,
+            y: -bound,
+        },
+        Point {
+            x: bound,
+            y: -bound,
+        },
+        Point { x: bound, y: bound },
+        Point {
+            x: -bound,
+            y: bound,
+        },
+    ];
+
+    let mut sorted: Vec<HalfPlane> = half_planes
+        .iter()
+        .filter(|h| h.direction.x != 0.0 || h.direction.y != 0.0)
+        .copied()
+        .chain((0..4).map(|i| HalfPlane::through(corners[i], corners[(i + 1) % 4])))
+        .collect();
+    sorted.sort_by(|a, b| a.angle().partial_cmp(&b.angle()).unwrap());
+
+    let mut deque: VecDeque<HalfPlane> = VecDeque::new();
+    for half_plane in sorted {
+        while deque.len() > 1
+            && half_plane
+                .is_outside(&deque[deque.len() - 1].boundary_intersection(&deque[deque.len() - 2]))
+        {
+            deque.pop_back();
+        }
+        while deque.len() > 1 && half_plane.is_outside(&deque[0].boundary_intersection(&deque[1])) {
+            deque.pop_front();
+        }
+        if let Some(last) = deque.back() {
+            // sine of the angle between both
+            let cross = (half_plane.direction.x * last.direction.y
+                - half_plane.direction.y * last.direction.x)
+                / (half_plane.direction.x.hypot(half_plane.direction.y)
+                    * last.direction.x.hypot(last.direction.y));
+            if cross.abs() < EPSILON {
+                if half_plane.direction.x * last.direction.x
+                    + half_plane.direction.y * last.direction.y
+                    < 0.0
+                {
+                    // opposite half-planes left next to each other => nothing remains
+                    return HalfPlaneIntersection::Empty;
+                }
+                if half_plane.is_outside(&last.point) {
+                    // the new one is more restrictive
+                    deque.pop_back();
+                } else {
+                    continue;
+                }
+            }
+        }
+        deque.push_back(half_plane);
+    }
+    while deque.len() > 2
+        && deque[0]
+            .is_outside(&deque[deque.len() - 1].boundary_intersection(&deque[deque.len() - 2]))
+    {
+        deque.pop_back();
+    }
+    while deque.len() > 2
+        && deque[deque.len() - 1].is_outside(&deque[0].boundary_intersection(&deque[1]))
+    {
+        deque.pop_front();
+    }
+    if deque.len() < 3 {
+        return HalfPlaneIntersection::Empty;
+    }
+
+    let vertices: Vec<Point> = (0..deque.len())
+        .map(|i| deque[i].boundary_intersection(&deque[(i + 1) % deque.len()]))
+        .collect();
+    let polygon = Polygon::new(vertices);
+    if polygon.area() <= EPSILON {
+        return HalfPlaneIntersection::Empty;
+    }
+    if polygon
+        .exterior
+        .iter()
+        .any(|p| p.x.abs() >= bound * (1.0 - 1E-9) || p.y.abs() >= bound * (1.0 - 1E-9))
+    {
+        return HalfPlaneIntersection::Unbounded;
+    }
+    HalfPlaneIntersection::Bounded(polygon)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn p(x: f64, y: f64) -> Point {
+        Point { x, y }
+    }
+
+    #[test]
+    fn square() {
+        let half_planes = [
+            HalfPlane::through(p(0.0, 0.0), p(1.0, 0.0)),
+            HalfPlane::through(p(1.0, 0.0), p(1.0, 1.0)),
+            HalfPlane::through(p(1.0, 1.0), p(0.0, 1.0)),
+            HalfPlane::through(p(0.0, 1.0), p(0.0, 0.0)),
+            // redundant
+            HalfPlane::through(p(-1.0, -1.0), p(2.0, -1.0)),
+        ];
+        match intersect_half_planes(&half_planes) {
+            HalfPlaneIntersection::Bounded(polygon) => {
+                assert!((polygon.area() - 1.0).abs() < 1e-9);
+                assert_eq!(polygon.vertex_count(), 4);
+            }
+            other => panic!("expected a square, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn empty_and_unbounded() {
+        let strip = [
+            HalfPlane::through(p(0.0, 0.0), p(1.0, 0.0)),
+            HalfPlane::through(p(1.0, 1.0), p(0.0, 1.0)),
+        ];
+        assert_eq!(
+            intersect_half_planes(&strip),
+            HalfPlaneIntersection::Unbounded
+        );
+
+        let disjoint = [
+            HalfPlane::through(p(0.0, 1.0), p(1.0, 1.0)),
+            HalfPlane::through(p(1.0, 0.0), p(0.0, 0.0)),
+        ];
+        assert_eq!(
+            intersect_half_planes(&disjoint),
+            HalfPlaneIntersection::Empty
+        );
+    }
+
+    #[test]
+    fn short_edges_are_not_parallel() {
+        // neighbouring edges of about 1e-6 differ by less than 6 degrees
+        let n = 64;
+        let tiny = Polygon::new(
+            (0..n)
+                .map(|i| {
+                    let angle = 2.0 * std::f64::consts::PI * i as f64 / n as f64;
+                    p(1.0 + 1e-5 * angle.cos(), 1.0 + 1e-5 * angle.sin())
+                })
+                .collect(),
+        );
+        let kernel = tiny.kernel().unwrap();
+        assert_eq!(kernel.vertex_count(), n);
+        assert!((kernel.area() / tiny.area() - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn kernel() {
+        // star shaped: the kernel is the small square in the middle
+        let cross = Polygon::new(vec![
+            p(1.0, 0.0),
+            p(2.0, 0.0),
+            p(2.0, 1.0),
+            p(3.0, 1.0),
+            p(3.0, 2.0),
+            p(2.0, 2.0),
+            p(2.0, 3.0),
+            p(1.0, 3.0),
+            p(1.0, 2.0),
+            p(0.0, 2.0),
+            p(0.0, 1.0),
+            p(1.0, 1.0),
+        ]);
+        let kernel = cross.kernel().unwrap();
+        assert!((kernel.area() - 1.0).abs() < 1e-9);
+
+        // two rooms connected by a long corridor can not be seen from a single point
+        let rooms = Polygon::new(vec![
+            p(0.0, 0.0),
+            p(2.0, 0.0),
+            p(2.0, 0.9),
+            p(8.0, 0.9),
+            p(8.0, 0.0),
+            p(10.0, 0.0),
+            p(10.0, 2.0),
+            p(8.0, 2.0),
+            p(8.0, 1.1),
+            p(2.0, 1.1),
+            p(2.0, 2.0),
+            p(0.0, 2.0),
+        ]);
+        assert!(!rooms.is_star_shaped());
+    }
+}
@@ -1,6 +1,8 @@
 use crate::lib::{ccw::ccw, point::Point};
 
+// repr(C): binary lines files store the fields in this order
 #[derive(Clone, Debug, PartialEq)]
+#[repr(C)]
 pub struct Line {
     pub p1: Point,
     pub p2: Point,
@@ -1,25 +1,126 @@
 use crate::lib::common::EPSILON;
 use crate::lib::line::Line;
-use std::cmp::Ordering;
+use crate::lib::point::Point;
+use std::{
+    cell::{Cell, RefCell},
+    cmp::Ordering,
+    rc::Rc,
+};
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SweepPosition {
+    pub point: Point,
+    // order as right after the sweep passed `point`, otherwise as right before it
+    pub after: bool,
+}
+
+// The position of a sweep and the segments passing through it. The sweep decides which
+// segments pass through the position, the comparator only looks up their rank.
+#[derive(Debug)]
+pub struct SweepLine {
+    position: Cell<SweepPosition>,
+    before: RefCell<Ranks>,
+    after: RefCell<Ranks>,
+}
+
+impl SweepLine {
+    pub fn get(&self) -> SweepPosition {
+        self.position.get()
+    }
+
+    pub fn set(&self, position: SweepPosition) {
+        self.position.set(position);
+    }
+
+    // The ids of the segments through the position, bottom to top right before and right after
+    // it. They are exactly at the height of the position and ordered by their index here.
+    pub fn set_through(&self, before: &[usize], after: &[usize]) {
+        self.before.borrow_mut().set(before);
+        self.after.borrow_mut().set(after);
+    }
+
+    fn rank(&self, id: usize) -> Option<usize> {
+        if self.position.get().after {
+            self.after.borrow().get(id)
+        } else {
+            self.before.borrow().get(id)
+        }
+    }
+}
+
+// rank by segment id, NONE for the segments not passing through
+#[derive(Debug, Default)]
+struct Ranks {
+    rank: Vec<usize>,
+    ids: Vec<usize>,
+}
+
+const NONE: usize = usize::MAX;
+
+impl Ranks {
+    fn set(&mut self, ids: &[usize]) {
+        for &id in &self.ids {
+            self.rank[id] = NONE;
+        }
+        for (rank, &id) in ids.iter().enumerate() {
+            if id >= self.rank.len() {
+                self.rank.resize(id + 1, NONE);
+            }
+            self.rank[id] = rank;
+        }
+        self.ids = ids.to_vec();
+    }
+
+    fn get(&self, id: usize) -> Option<usize> {
+        self.rank.get(id).copied().filter(|&rank| rank != NONE)
+    }
+}
+
+// One position shared by all entries of a sweep, moving it reorders all of them at once.
+pub type SharedPosition = Rc<SweepLine>;
+
+pub fn shared_position() -> SharedPosition {
+    Rc::new(SweepLine {
+        position: Cell::new(SweepPosition {
+            point: Point { x: 0.0, y: 0.0 },
+            after: false,
+        }),
+        before: RefCell::default(),
+        after: RefCell::default(),
+    })
+}
+
+// A segment on the sweep line. Entries compare by their height at the shared sweep position.
 #[derive(Clone, Debug)]
 pub struct LineWithOrd {
     pub line: Line,
-    pub sweep_x: f64,
+    // index of the segment in the input of the sweep
+    pub id: usize,
+    pub sweep: SharedPosition,
 }
 
 impl LineWithOrd {
-    pub fn new(line: Line, sweep_x: f64) -> Self {
-        Self { line, sweep_x }
+    pub fn new(line: Line, id: usize, sweep: SharedPosition) -> Self {
+        Self { line, id, sweep }
     }
 
-    pub fn y_at(&self) -> f64 {
+    // A vertical segment is at the height of the sweep position, as far as it reaches.
+    pub fn y_at(&self, at: &Point) -> f64 {
         let (x0, y0) = (self.line.p1.x, self.line.p1.y);
         let (x1, y1) = (self.line.p2.x, self.line.p2.y);
         if (x1 - x0).abs() < EPSILON {
-            return y0;
+            return at.y.clamp(y0.min(y1), y0.max(y1));
+        }
+        y0 + ((at.x - x0) / (x1 - x0)) * (y1 - y0)
+    }
+
+    // vertical segments are steeper than everything else
+    pub fn slope(&self) -> f64 {
+        let dx = self.line.p2.x - self.line.p1.x;
+        if dx.abs() < EPSILON {
+            return f64::INFINITY;
         }
-        y0 + ((self.sweep_x - x0) / (x1 - x0)) * (y1 - y0)
+        (self.line.p2.y - self.line.p1.y) / dx
     }
 }
 
@@ -27,18 +128,47 @@ impl Eq for LineWithOrd {}
 
 impl PartialEq for LineWithOrd {
     fn eq(&self, other: &Self) -> bool {
-        self.y_at().partial_cmp(&other.y_at()).unwrap() == Ordering::Equal
+        self.cmp(other) == Ordering::Equal
     }
 }
 
 impl PartialOrd for LineWithOrd {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.y_at().partial_cmp(&other.y_at())
+        Some(self.cmp(other))
     }
 }
 
 impl Ord for LineWithOrd {
+    // Heights are compared exactly, a tolerance would make the order intransitive. Segments
+    // through the position are at its height, at the same height the ones not passing
+    // through come first.
     fn cmp(&self, other: &Self) -> Ordering {
-        self.partial_cmp(other).unwrap()
+        let position = self.sweep.get();
+        let at = &position.point;
+        let ranks = (self.sweep.rank(self.id), self.sweep.rank(other.id));
+        let height = |entry: &Self, rank: Option<usize>| match rank {
+            Some(_) => at.y,
+            None => entry.y_at(at),
+        };
+        let heights = height(self, ranks.0).total_cmp(&height(other, ranks.1));
+        if heights != Ordering::Equal {
+            return heights;
+        }
+        match ranks {
+            (Some(a), Some(b)) => return a.cmp(&b),
+            (Some(_), None) => return Ordering::Greater,
+            (None, Some(_)) => return Ordering::Less,
+            (None, None) => {}
+        }
+        // segments through the same point: the steeper one is below before and above after it
+        // (partial_cmp, since total_cmp would order -0.0 before 0.0)
+        let slopes = self.slope().partial_cmp(&other.slope()).unwrap();
+        let slopes = if position.after {
+            slopes
+        } else {
+            slopes.reverse()
+        };
+        // overlapping segments keep the order of their ids
+        slopes.then(self.id.cmp(&other.id))
     }
 }
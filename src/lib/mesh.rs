@@ -0,0 +1,539 @@
+use crate::lib::data::DataError;
+use std::{fmt::Write, fs, str::FromStr};
+
+// d-dimensional points, stored flat: point i is coordinates[i * d..(i + 1) * d].
+#[derive(Clone, Debug, PartialEq)]
+pub struct PointCloud {
+    pub dimension: usize,
+    pub coordinates: Vec<f64>,
+}
+
+// Polyhedral mesh, every face is a list of vertex indices.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mesh {
+    pub vertices: PointCloud,
+    pub faces: Vec<Vec<usize>>,
+}
+
+impl PointCloud {
+    pub fn new(dimension: usize) -> Self {
+        Self {
+            dimension,
+            coordinates: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.coordinates.len() / self.dimension.max(1)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.coordinates.is_empty()
+    }
+
+    pub fn push(&mut self, point: &[f64]) {
+        assert_eq!(point.len(), self.dimension);
+        self.coordinates.extend_from_slice(point);
+    }
+
+    pub fn point(&self, i: usize) -> &[f64] {
+        &self.coordinates[i * self.dimension..(i + 1) * self.dimension]
+    }
+
+    pub fn points(&self) -> impl Iterator<Item = &[f64]> {
+        self.coordinates.chunks_exact(self.dimension.max(1))
+    }
+}
+
+// rbox output / qhull input:
+//   3 rbox 10 D3      dimension, the rest of the line is a comment
+//   10                number of points
+//   0.1 -0.2 0.3      one point per row
+pub fn parse_rbox(text: &str, path: &str) -> Result<PointCloud, DataError> {
+    let mut tokens = Tokens::new(text, path);
+    let dimension: usize = tokens.number("dimension")?;
+    tokens.skip_line();
+    let count: usize = tokens.number("number of points")?;
+    tokens.skip_line();
+    let points = read_points(&mut tokens, dimension, count)?;
+    tokens.expect_end()?;
+    Ok(points)
+}
+
+pub fn read_rbox(path: &str) -> Result<PointCloud, DataError> {
+    parse_rbox(&read_file(path)?, path)
+}
+
+pub fn write_rbox(points: &PointCloud, comment: &str) -> String {
+    let mut text = format!("{} {}\n{}\n", points.dimension, comment, points.len());
+    write_rows(&mut text, points);
+    text
+}
+
+// OFF as written by most tools ("OFF", three dimensions), "nOFF" with an explicit dimension,
+// and the output of qhull's 'o' option, which starts with the dimension instead of a keyword.
+// Colors after the vertex indices of a face are ignored.
+pub fn parse_off(text: &str, path: &str) -> Result<Mesh, DataError> {
+    let mut tokens = Tokens::new(text, path);
+    let dimension = match tokens.peek() {
+        Some("OFF") => {
+            tokens.next();
+            3
+        }
+        Some("nOFF") => {
+            tokens.next();
+            tokens.number("dimension")?
+        }
+        _ => {
+            let dimension = tokens.number("dimension or OFF keyword")?;
+            tokens.skip_line();
+            dimension
+        }
+    };
+    let vertex_count: usize = tokens.number("number of vertices")?;
+    let face_count: usize = tokens.number("number of faces")?;
+    // number of edges, not used
+    tokens.skip_line();
+
+    let vertices = read_points(&mut tokens, dimension, vertex_count)?;
+    let mut faces = Vec::new();
+    for _ in 0..face_count {
+        let size: usize = tokens.number("face size")?;
+        faces.push(read_indices(&mut tokens, size, vertex_count)?);
+        tokens.skip_line();
+    }
+    tokens.expect_end()?;
+    Ok(Mesh { vertices, faces })
+}
+
+pub fn read_off(path: &str) -> Result<Mesh, DataError> {
+    parse_off(&read_file(path)?, path)
+}
+
+pub fn write_off(mesh: &Mesh) -> String {
+    let mut text = if mesh.vertices.dimension == 3 {
+        "OFF\n".to_string()
+    } else {
+        format!("nOFF\n{}\n", mesh.vertices.dimension)
+    };
+    writeln!(text, "{} {} 0", mesh.vertices.len(), mesh.faces.len()).unwrap();
+    write_rows(&mut text, &mesh.vertices);
+    write_faces(&mut text, &mesh.faces);
+    text
+}
+
+// ASCII PLY. Coordinates are the vertex properties x, y, z (and x3, x4, ... for more
+// dimensions), all other vertex properties and elements are skipped.
+pub fn parse_ply(text: &str, path: &str) -> Result<Mesh, DataError> {
+    let mut tokens = Tokens::new(text, path);
+    if tokens.next() != Some("ply") {
+        return Err(tokens.error("missing 'ply' magic"));
+    }
+    tokens.skip_line();
+
+    let mut elements: Vec<PlyElement> = Vec::new();
+    loop {
+        let keyword = tokens
+            .next()
+            .ok_or_else(|| tokens.error("missing end_header"))?;
+        match keyword {
+            "format" => {
+                if tokens.next() != Some("ascii") {
+                    return Err(tokens.error("only ASCII PLY is supported"));
+                }
+            }
+            "comment" | "obj_info" => {}
+            "element" => {
+                let name = tokens.word("element name")?.to_string();
+                let count = tokens.number("element count")?;
+                elements.push(PlyElement {
+                    name,
+                    count,
+                    properties: Vec::new(),
+                });
+            }
+            "property" => {
+                let element = elements
+                    .last_mut()
+                    .ok_or_else(|| tokens.error("property before any element"))?;
+                let is_list = tokens.peek() == Some("list");
+                if is_list {
+                    // list <count type> <item type>
+                    tokens.next();
+                    tokens.word("list count type")?;
+                }
+                tokens.word("property type")?;
+                let name = tokens.word("property name")?.to_string();
+                element.properties.push((name, is_list));
+            }
+            "end_header" => {
+                tokens.skip_line();
+                break;
+            }
+            other => return Err(tokens.error(&format!("unexpected '{}' in header", other))),
+        }
+        tokens.skip_line();
+    }
+
+    let vertex_element = elements.iter().find(|element| element.name == "vertex");
+    let dimension = vertex_element.map_or(0, |element| {
+        element
+            .properties
+            .iter()
+            .filter(|(name, _)| coordinate_index(name).is_some())
+            .count()
+    });
+    let vertex_count = vertex_element.map_or(0, |element| element.count);
+    let mut vertices = PointCloud::new(dimension);
+    let mut faces = Vec::new();
+
+    for PlyElement {
+        name,
+        count,
+        properties,
+    } in &elements
+    {
+        // every row reads at least one value, so the count can not outrun the file
+        if properties.is_empty() && *count > 0 {
+            return Err(tokens.error(&format!("element {} without properties", name)));
+        }
+        for _ in 0..*count {
+            let mut point = vec![0.0; dimension];
+            for (property, is_list) in properties {
+                if *is_list {
+                    let size: usize = tokens.number("list size")?;
+                    if name == "face"
+                        && matches!(property.as_str(), "vertex_indices" | "vertex_index")
+                    {
+                        faces.push(read_indices(&mut tokens, size, vertex_count)?);
+                    } else {
+                        for _ in 0..size {
+                            tokens.number::<f64>("list item")?;
+                        }
+                    }
+                } else {
+                    let value: f64 = tokens.number(property)?;
+                    if let (true, Some(i)) = (name == "vertex", coordinate_index(property)) {
+                        if i >= dimension {
+                            return Err(tokens.error(&format!(
+                                "property {} without {}",
+                                property,
+                                coordinate_name(i - 1)
+                            )));
+                        }
+                        point[i] = value;
+                    }
+                }
+            }
+            if name == "vertex" {
+                vertices.push(&point);
+            }
+            tokens.skip_line();
+        }
+    }
+    tokens.expect_end()?;
+    Ok(Mesh { vertices, faces })
+}
+
+struct PlyElement {
+    name: String,
+    count: usize,
+    // (name, is list)
+    properties: Vec<(String, bool)>,
+}
+
+pub fn read_ply(path: &str) -> Result<Mesh, DataError> {
+    parse_ply(&read_file(path)?, path)
+}
+
+pub fn write_ply(mesh: &Mesh) -> String {
+    let mut text = "ply\nformat ascii 1.0\n".to_string();
+    writeln!(text, "element vertex {}", mesh.vertices.len()).unwrap();
+    for i in 0..mesh.vertices.dimension {
+        writeln!(text, "property double {}", coordinate_name(i)).unwrap();
+    }
+    writeln!(text, "element face {}", mesh.faces.len()).unwrap();
+    text.push_str("property list uchar int vertex_indices\nend_header\n");
+    write_rows(&mut text, &mesh.vertices);
+    write_faces(&mut text, &mesh.faces);
+    text
+}
+
+fn coordinate_name(i: usize) -> String {
+    match i {
+        0 => "x".to_string(),
+        1 => "y".to_string(),
+        2 => "z".to_string(),
+        _ => format!("x{}", i),
+    }
+}
+
+fn coordinate_index(name: &str) -> Option<usize> {
+    match name {
+        "x" => Some(0),
+        "y" => Some(1),
+        "z" => Some(2),
+        _ => name
+            .strip_prefix('x')
+            .and_then(|i| i.parse().ok())
+            .filter(|&i| i >= 3),
+    }
+}
+
+fn write_rows(text: &mut String, points: &PointCloud) {
+    for point in points.points() {
+        let row: Vec<String> = point.iter().map(f64::to_string).collect();
+        text.push_str(&row.join(" "));
+        text.push('\n');
+    }
+}
+
+fn write_faces(text: &mut String, faces: &[Vec<usize>]) {
+    for face in faces {
+        write!(text, "{}", face.len()).unwrap();
+        for index in face {
+            write!(text, " {}", index).unwrap();
+        }
+        text.push('\n');
+    }
+}
+
+fn read_points(
+    tokens: &mut Tokens,
+    dimension: usize,
+    count: usize,
+) -> Result<PointCloud, DataError> {
+    // the counts come from the header, so nothing is reserved for them: a short file with a
+    // huge count fails at its end instead of allocating
+    if dimension == 0 {
+        return Err(tokens.error("the dimension must be positive"));
+    }
+    if dimension.checked_mul(count).is_none() {
+        return Err(tokens.error("too many coordinates"));
+    }
+    let mut points = PointCloud::new(dimension);
+    for _ in 0..count {
+        for _ in 0..dimension {
+            let value: f64 = tokens.number("coordinate")?;
+            if !value.is_finite() {
+                return Err(tokens.error("coordinates must be finite"));
+            }
+            points.coordinates.push(value);
+        }
+        tokens.skip_line();
+    }
+    Ok(points)
+}
+
+fn read_indices(
+    tokens: &mut Tokens,
+    size: usize,
+    vertex_count: usize,
+) -> Result<Vec<usize>, DataError> {
+    (0..size)
+        .map(|_| {
+            let index: usize = tokens.number("vertex index")?;
+            if index >= vertex_count {
+                return Err(tokens.error(&format!(
+                    "vertex index {} out of range ({} vertices)",
+                    index, vertex_count
+                )));
+            }
+            Ok(index)
+        })
+        .collect()
+}
+
+fn read_file(path: &str) -> Result<String, DataError> {
+    fs::read_to_string(path).map_err(|e| DataError {
+        path: path.to_string(),
+        line: 0,
+        column: 0,
+        reason: e.to_string(),
+    })
+}
+
+// Whitespace separated words with their position, '#' starts a comment.
+struct Tokens<'a> {
+    path: &'a str,
+    rows: std::iter::Enumerate<std::str::Lines<'a>>,
+    // words of the current row as (column, word)
+    words: Vec<(usize, &'a str)>,
+    next_word: usize,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(text: &'a str, path: &'a str) -> Self {
+        Self {
+            path,
+            rows: text.lines().enumerate(),
+            words: Vec::new(),
+            next_word: 0,
+            line: 0,
+            column: 0,
+        }
+    }
+
+    fn error(&self, reason: &str) -> DataError {
+        DataError {
+            path: self.path.to_string(),
+            line: self.line,
+            column: self.column,
+            reason: reason.to_string(),
+        }
+    }
+
+    fn fill(&mut self) -> bool {
+        while self.next_word >= self.words.len() {
+            let Some((index, row)) = self.rows.next() else {
+                return false;
+            };
+            let content = row.split('#').next().unwrap_or("");
+            self.line = index + 1;
+            self.column = 1;
+            self.next_word = 0;
+            self.words = content
+                .split_whitespace()
+                .map(|word| {
+                    let offset = word.as_ptr() as usize - content.as_ptr() as usize;
+                    (content[..offset].chars().count() + 1, word)
+                })
+                .collect();
+        }
+        true
+    }
+
+    fn peek(&mut self) -> Option<&'a str> {
+        if !self.fill() {
+            return None;
+        }
+        Some(self.words[self.next_word].1)
+    }
+
+    fn next(&mut self) -> Option<&'a str> {
+        if !self.fill() {
+            return None;
+        }
+        let (column, word) = self.words[self.next_word];
+        self.column = column;
+        self.next_word += 1;
+        Some(word)
+    }
+
+    fn word(&mut self, what: &str) -> Result<&'a str, DataError> {
+        self.next()
+            .ok_or_else(|| self.error(&format!("unexpected end of file, expected {}", what)))
+    }
+
+    fn number<T: FromStr>(&mut self, what: &str) -> Result<T, DataError> {
+        let word = self.word(what)?;
+        word.parse()
+            .map_err(|_| self.error(&format!("expected {}, found '{}'", what, word)))
+    }
+
+    // drops the rest of the current row
+    fn skip_line(&mut self) {
+        self.next_word = self.words.len();
+    }
+
+    fn expect_end(&mut self) -> Result<(), DataError> {
+        match self.next() {
+            Some(word) => Err(self.error(&format!("unexpected '{}' after the data", word))),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tetrahedron() -> Mesh {
+        let mut vertices = PointCloud::new(3);
+        for point in [
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [0.0, 0.0, 1.5],
+        ] {
+            vertices.push(&point);
+        }
+        Mesh {
+            vertices,
+            faces: vec![vec![0, 2, 1], vec![0, 1, 3], vec![1, 2, 3], vec![0, 3, 2]],
+        }
+    }
+
+    #[test]
+    fn rbox() {
+        let points = parse_rbox("3 rbox 2 D3\n2\n0.1 -0.2 3e-1\n  1 2 3\n", "rbox").unwrap();
+        assert_eq!(points.len(), 2);
+        assert_eq!(points.point(0), &[0.1, -0.2, 0.3]);
+        assert_eq!(
+            parse_rbox(&write_rbox(&points, "copy"), "rbox").unwrap(),
+            points
+        );
+
+        let error = parse_rbox("2 rbox\n2\n0 0\n1\n", "rbox").unwrap_err();
+        assert_eq!(
+            (error.line, error.reason.as_str()),
+            (4, "unexpected end of file, expected coordinate")
+        );
+    }
+
+    #[test]
+    fn off_and_ply_round_trip() {
+        let mesh = tetrahedron();
+        assert_eq!(parse_off(&write_off(&mesh), "off").unwrap(), mesh);
+        assert_eq!(parse_ply(&write_ply(&mesh), "ply").unwrap(), mesh);
+
+        let mut square = PointCloud::new(4);
+        square.push(&[1.0, 2.0, 3.0, 4.0]);
+        let mesh4 = Mesh {
+            vertices: square,
+            faces: Vec::new(),
+        };
+        assert_eq!(parse_off(&write_off(&mesh4), "off").unwrap(), mesh4);
+        assert_eq!(parse_ply(&write_ply(&mesh4), "ply").unwrap(), mesh4);
+    }
+
+    #[test]
+    fn qhull_output_and_foreign_ply() {
+        // qhull 'o': dimension, counts, points, facets
+        let qhull = "2\n3 1 3\n0 0\n1 0\n0 1\n3 0 1 2\n";
+        let mesh = parse_off(qhull, "qhull").unwrap();
+        assert_eq!(mesh.vertices.dimension, 2);
+        assert_eq!(mesh.faces, vec![vec![0, 1, 2]]);
+
+        let ply = "ply\nformat ascii 1.0\ncomment made elsewhere\nelement vertex 3\n\
+                   property float x\nproperty float y\nproperty float z\nproperty uchar red\n\
+                   element face 1\nproperty list uchar int vertex_indices\nend_header\n\
+                   0 0 0 255\n1 0 0 0\n0 1 0 0\n3 0 1 2\n";
+        let mesh = parse_ply(ply, "ply").unwrap();
+        assert_eq!(mesh.vertices.point(1), &[1.0, 0.0, 0.0]);
+        assert_eq!(mesh.faces, vec![vec![0, 1, 2]]);
+
+        assert!(parse_off("OFF\n1 1 0\n0 0 0\n3 0 1 2\n", "off").is_err());
+    }
+
+    #[test]
+    fn huge_counts_in_the_header() {
+        let error = parse_rbox("3\n18446744073709551615\n0 0 0\n", "rbox").unwrap_err();
+        assert_eq!(error.reason, "too many coordinates");
+        let error = parse_rbox("3\n1000000000000000\n0 0 0\n", "rbox").unwrap_err();
+        assert_eq!(error.reason, "unexpected end of file, expected coordinate");
+        let error = parse_rbox("0\n1000000000000000\n", "rbox").unwrap_err();
+        assert_eq!(error.reason, "the dimension must be positive");
+
+        let error = parse_off("OFF\n1 100000000000000000 0\n0 0 0\n", "off").unwrap_err();
+        assert_eq!(error.reason, "unexpected end of file, expected face size");
+        let error = parse_off("nOFF\n4\n18446744073709551615 0 0\n", "off").unwrap_err();
+        assert_eq!(error.reason, "too many coordinates");
+
+        let ply = "ply\nformat ascii 1.0\nelement nothing 1000000000000000\nend_header\n";
+        let error = parse_ply(ply, "ply").unwrap_err();
+        assert_eq!(error.reason, "element nothing without properties");
+    }
+}
@@ -0,0 +1,131 @@
+use crate::lib::{
+    boolean::union,
+    point::Point,
+    polygon::{MultiPolygon, Polygon},
+    triangulation::{convex_decomposition, is_convex, normalized_ring},
+};
+
+// Minkowski sum of two convex polygons in O(n + m) by merging the edges sorted by angle.
+pub fn minkowski_sum_convex(a: &Polygon, b: &Polygon) -> Polygon {
+    Polygon::new(convex_sum(
+        &normalized_ring(&a.exterior),
+        &normalized_ring(&b.exterior),
+    ))
+}
+
+// Minkowski sum of two simple polygons (holes are ignored). Non-convex polygons are split into
+// convex pieces, the pieces are summed pairwise and the union of all partial sums is returned.
+pub fn minkowski_sum(a: &Polygon, b: &Polygon) -> MultiPolygon {
+    if is_convex(&a.exterior) && is_convex(&b.exterior) {
+        return vec![minkowski_sum_convex(a, b)];
+    }
+    let pieces_a = convex_decomposition(&a.exterior);
+    let pieces_b = convex_decomposition(&b.exterior);
+
+    let mut sums = Vec::with_capacity(pieces_a.len() * pieces_b.len());
+    for piece_a in &pieces_a {
+        for piece_b in &pieces_b {
+            sums.push(Polygon::new(convex_sum(piece_a, piece_b)));
+        }
+    }
+    union(&sums)
+}
+
+// Configuration space obstacle for a robot translating with its reference point at the origin:
+// obstacle ⊕ (-robot).
+pub fn configuration_space_obstacle(obstacle: &Polygon, robot: &Polygon) -> MultiPolygon {
+    let reflected = Polygon::new(
+        robot
+            .exterior
+            .iter()
+            .map(|p| Point { x: -p.x, y: -p.y })
+            .collect(),
+    );
+    minkowski_sum(obstacle, &reflected)
+}
+
+// Both rings have to be convex, open and counter-clockwise.
+fn convex_sum(a: &[Point], b: &[Point]) -> Vec<Point> {
+    let a = start_at_lowest(a);
+    let b = start_at_lowest(b);
+    let (n, m) = (a.len(), b.len());
+
+    let mut result = Vec::with_capacity(n + m);
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        let point = a[i % n] + b[j % m];
+        if result.last() != Some(&point) {
+            result.push(point);
+        }
+        let edge_a = a[(i + 1) % n] - a[i % n];
+        let edge_b = b[(j + 1) % m] - b[j % m];
+        let cross = edge_a.x * edge_b.y - edge_a.y * edge_b.x;
+        if j >= m || (i < n && cross > 0.0) {
+            i += 1;
+        } else if i >= n || cross < 0.0 {
+            j += 1;
+        } else {
+            i += 1;
+            j += 1;
+        }
+    }
+    result
+}
+
+fn start_at_lowest(ring: &[Point]) -> Vec<Point> {
+    let lowest = (0..ring.len())
+        .min_by(|&i, &j| {
+            (ring[i].y, ring[i].x)
+                .partial_cmp(&(ring[j].y, ring[j].x))
+                .unwrap()
+        })
+        .unwrap_or(0);
+    ring[lowest..]
+        .iter()
+        .chain(&ring[..lowest])
+        .copied()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(x: f64, y: f64, size: f64) -> Polygon {
+        Polygon::new(vec![
+            Point { x, y },
+            Point { x: x + size, y },
+            Point {
+                x: x + size,
+                y: y + size,
+            },
+            Point { x, y: y + size },
+        ])
+    }
+
+    #[test]
+    fn convex_squares() {
+        let sum = minkowski_sum_convex(&square(0.0, 0.0, 2.0), &square(-0.5, -0.5, 1.0));
+        assert_eq!(sum.vertex_count(), 4);
+        assert!((sum.area() - 9.0).abs() < 1e-9);
+        assert!(sum.contains(&Point { x: -0.4, y: 2.4 }));
+    }
+
+    #[test]
+    fn l_shape_and_square() {
+        let l_shape = Polygon::new(vec![
+            Point { x: 0.0, y: 0.0 },
+            Point { x: 3.0, y: 0.0 },
+            Point { x: 3.0, y: 1.0 },
+            Point { x: 1.0, y: 1.0 },
+            Point { x: 1.0, y: 3.0 },
+            Point { x: 0.0, y: 3.0 },
+        ]);
+        let sum = minkowski_sum(&l_shape, &square(0.0, 0.0, 1.0));
+        assert_eq!(sum.len(), 1);
+        assert!(sum[0].holes.is_empty());
+        // the L grows by one unit to the right and to the top
+        assert!((sum[0].area() - 12.0).abs() < 1e-9);
+        assert_eq!(sum[0].vertex_count(), 6);
+    }
+}
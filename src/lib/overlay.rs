@@ -0,0 +1,179 @@
+use crate::lib::{
+    dcel::{Dcel, OUTER_FACE},
+    line::Line,
+    point::Point,
+    point_location::SlabDecomposition,
+};
+
+// Overlay of two labelled maps, e.g. states and a grid of analysis cells or two versions of
+// one map. Regions are given like for `SlabDecomposition`: a label and its rings, even-odd.
+//
+// The edges of both maps are split in one sweep and form a DCEL. Every bounded face of it lies
+// in at most one region of each map, found by locating a point inside the face in both maps.
+pub struct Overlay<A, B> {
+    pub dcel: Dcel,
+    pub first: Vec<A>,
+    pub second: Vec<B>,
+    // for every face of `dcel` the index of the region of each map it lies in, None outside
+    pub regions: Vec<(Option<usize>, Option<usize>)>,
+}
+
+impl<A, B> Overlay<A, B> {
+    pub fn new(
+        first: impl IntoIterator<Item = (A, Vec<Vec<Point>>)>,
+        second: impl IntoIterator<Item = (B, Vec<Vec<Point>>)>,
+    ) -> Self {
+        let first: Vec<_> = first.into_iter().collect();
+        let second: Vec<_> = second.into_iter().collect();
+        let edges: Vec<Line> = first
+            .iter()
+            .map(|(_, rings)| rings)
+            .chain(second.iter().map(|(_, rings)| rings))
+            .flatten()
+            .flat_map(|ring| ring.windows(2).map(|w| Line::new(w[0], w[1])))
+            .collect();
+        let dcel = Dcel::from_segments(&edges);
+
+        let first = SlabDecomposition::new(first);
+        let second = SlabDecomposition::new(second);
+        let regions = (0..dcel.faces.len())
+            .map(|face| match dcel.interior_point(face) {
+                Some(point) => (first.locate_index(&point), second.locate_index(&point)),
+                None => (None, None),
+            })
+            .collect();
+        Self {
+            dcel,
+            first: first.into_labels(),
+            second: second.into_labels(),
+            regions,
+        }
+    }
+
+    pub fn labels(&self, face: usize) -> (Option<&A>, Option<&B>) {
+        let (a, b) = self.regions[face];
+        (a.map(|a| &self.first[a]), b.map(|b| &self.second[b]))
+    }
+
+    // The bounded faces, without the holes of both maps.
+    pub fn faces(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.dcel.faces.len())
+            .filter(|&face| face != OUTER_FACE && self.regions[face] != (None, None))
+    }
+
+    // areas[i][j] is the area shared by region i of the first and region j of the second map.
+    pub fn area_matrix(&self) -> Vec<Vec<f64>> {
+        let mut areas = vec![vec![0.0; self.second.len()]; self.first.len()];
+        for face in self.faces() {
+            if let (Some(a), Some(b)) = self.regions[face] {
+                areas[a][b] += self.dcel.area(face);
+            }
+        }
+        areas
+    }
+
+    // For two versions of a map: the faces that changed their label, including the ones only
+    // covered by one version.
+    pub fn changed_faces(&self) -> Vec<usize>
+    where
+        A: PartialEq<B>,
+    {
+        self.faces()
+            .filter(|&face| match self.labels(face) {
+                (Some(a), Some(b)) => a != b,
+                _ => true,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rectangle(x1: f64, y1: f64, x2: f64, y2: f64) -> Vec<Point> {
+        [(x1, y1), (x2, y1), (x2, y2), (x1, y2), (x1, y1)]
+            .iter()
+            .map(|&(x, y)| Point { x, y })
+            .collect()
+    }
+
+    type Cell = ((usize, usize), Vec<Vec<Point>>);
+
+    // cells of `size`, labelled (column, row)
+    fn grid(cells: usize, size: f64) -> Vec<Cell> {
+        let mut regions = Vec::new();
+        for column in 0..cells {
+            for row in 0..cells {
+                let (x, y) = (column as f64 * size, row as f64 * size);
+                regions.push(((column, row), vec![rectangle(x, y, x + size, y + size)]));
+            }
+        }
+        regions
+    }
+
+    #[test]
+    fn states_in_grid_cells() {
+        // two neighbours sharing a border, the first one with a hole, and a triangle
+        let states = vec![
+            (
+                "west",
+                vec![rectangle(1.0, 1.0, 5.0, 7.0), rectangle(2.0, 2.0, 3.0, 3.0)],
+            ),
+            ("east", vec![rectangle(5.0, 1.0, 7.0, 7.0)]),
+            (
+                "south",
+                vec![
+                    [(1.0, 0.0), (7.0, 0.0), (4.0, 1.0), (1.0, 0.0)]
+                        .iter()
+                        .map(|&(x, y)| Point { x, y })
+                        .collect(),
+                ],
+            ),
+        ];
+        let overlay = Overlay::new(states, grid(2, 4.0));
+        let areas = overlay.area_matrix();
+        let total = |row: &Vec<f64>| row.iter().sum::<f64>();
+        assert!((total(&areas[0]) - 23.0).abs() < 1e-9);
+        assert!((total(&areas[1]) - 12.0).abs() < 1e-9);
+        assert!((total(&areas[2]) - 3.0).abs() < 1e-9);
+        // west in the lower left cell (0, 0) is [1, 4] x [1, 4] without the hole
+        let lower_left = overlay.second.iter().position(|&c| c == (0, 0)).unwrap();
+        assert!((areas[0][lower_left] - 8.0).abs() < 1e-9);
+        // the grid covers all states
+        assert!(overlay.faces().all(|face| overlay.labels(face).1.is_some()));
+        // the hole of west and the cells around the states
+        let outside: f64 = overlay
+            .faces()
+            .filter(|&face| overlay.regions[face].0.is_none())
+            .map(|face| overlay.dcel.area(face))
+            .sum();
+        assert!((outside - (64.0 - 23.0 - 12.0 - 3.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn diff_of_two_versions() {
+        let old = vec![
+            ("a", vec![rectangle(0.0, 0.0, 2.0, 2.0)]),
+            ("b", vec![rectangle(2.0, 0.0, 4.0, 2.0)]),
+        ];
+        // the border moved to the right, and "b" grew upwards
+        let new = vec![
+            ("a", vec![rectangle(0.0, 0.0, 3.0, 2.0)]),
+            ("b", vec![rectangle(3.0, 0.0, 4.0, 3.0)]),
+        ];
+        let overlay = Overlay::new(old, new);
+        let changed = overlay.changed_faces();
+        assert_eq!(changed.len(), 2);
+        let mut areas: Vec<f64> = changed.iter().map(|&f| overlay.dcel.area(f)).collect();
+        areas.sort_by(f64::total_cmp);
+        assert_eq!(areas, vec![1.0, 2.0]);
+        for &face in &changed {
+            match overlay.labels(face) {
+                (Some(&"b"), Some(&"a")) => assert_eq!(overlay.dcel.area(face), 2.0),
+                (None, Some(&"b")) => assert_eq!(overlay.dcel.area(face), 1.0),
+                labels => panic!("unexpected change {:?}", labels),
+            }
+        }
+    }
+}
@@ -5,6 +5,7 @@ use std::ops::AddAssign;
 use std::ops::Sub;
 
 #[derive(Clone, Debug, PartialEq, Copy)]
+#[repr(C)]
 pub struct Point {
     pub x: f64,
     pub y: f64,
@@ -20,7 +21,7 @@ impl Point {
         self.x >= xmin && self.x <= xmax && self.y >= ymin && self.y <= ymax
     }
 
-    pub fn is_in_polygon(&self, polygon: &Vec<Point>) -> bool {
+    pub fn is_in_polygon(&self, polygon: &[Point]) -> bool {
         let point_not_in_polygon = Point { x: -1.0, y: -1.0 };
         let mut i = 1;
         while ccw(&point_not_in_polygon, self, &polygon[i]) == 0 {
@@ -43,6 +44,24 @@ impl Point {
         s % 2 != 0
     }
 
+    pub fn distance(&self, other: &Point) -> f64 {
+        ((other.x - self.x) * (other.x - self.x) + (other.y - self.y) * (other.y - self.y)).sqrt()
+    }
+
+    pub fn distance_to_segment(&self, a: &Point, b: &Point) -> f64 {
+        let dx = b.x - a.x;
+        let dy = b.y - a.y;
+        let length_squared = dx * dx + dy * dy;
+        if length_squared == 0.0 {
+            return self.distance(a);
+        }
+        let t = (((self.x - a.x) * dx + (self.y - a.y) * dy) / length_squared).clamp(0.0, 1.0);
+        self.distance(&Point {
+            x: a.x + t * dx,
+            y: a.y + t * dy,
+        })
+    }
+
     pub fn normalize(self) -> Self {
         let len = (self.x * self.x + self.y * self.y).sqrt();
         if len == 0.0 {
@@ -0,0 +1,266 @@
+use crate::lib::{point::Point, polygon::Polygon};
+
+// Point location by slab decomposition.
+//
+// The plane is cut into vertical slabs at every vertex x-coordinate. Inside a slab no edges cross,
+// so they are stored sorted from bottom to top together with the region right above every edge.
+// A query is two binary searches => O(log n). Building and memory are O(n * k) for k edges per
+// slab.
+//
+// Regions are given as sets of rings and use the even-odd rule, so holes need no special care.
+// Where regions overlap (e.g. an enclave that is not cut out of the surrounding region) the
+// innermost one, i.e. the one whose boundary was crossed last, wins. Rings with a non-finite
+// vertex (e.g. WKT's POINT EMPTY) are ignored.
+pub struct SlabDecomposition<L> {
+    labels: Vec<L>,
+    // (left endpoint, right endpoint, region)
+    edges: Vec<(Point, Point, usize)>,
+    xs: Vec<f64>,
+    slabs: Vec<Slab>,
+}
+
+struct Slab {
+    // sorted from bottom to top
+    edges: Vec<usize>,
+    // regions[k] = region between edges[k] and edges[k + 1]
+    regions: Vec<Option<usize>>,
+}
+
+impl<L> SlabDecomposition<L> {
+    pub fn new(regions: impl IntoIterator<Item = (L, Vec<Vec<Point>>)>) -> Self {
+        let mut labels = Vec::new();
+        let mut edges = Vec::new();
+        for (region, (label, rings)) in regions.into_iter().enumerate() {
+            labels.push(label);
+            for ring in rings {
+                if !ring.iter().all(|p| p.x.is_finite() && p.y.is_finite()) {
+                    continue;
+                }
+                for k in 0..ring.len() {
+                    let (a, b) = (ring[k], ring[(k + 1) % ring.len()]);
+                    // vertical edges never span the inside of a slab
+                    if a.x != b.x {
+                        edges.push(if a.x < b.x {
+                            (a, b, region)
+                        } else {
+                            (b, a, region)
+                        });
+                    }
+                }
+            }
+        }
+
+        let mut xs: Vec<f64> = edges.iter().flat_map(|e| [e.0.x, e.1.x]).collect();
+        xs.sort_by(f64::total_cmp);
+        xs.dedup();
+
+        let mut by_start: Vec<usize> = (0..edges.len()).collect();
+        by_start.sort_by(|&a, &b| edges[a].0.x.total_cmp(&edges[b].0.x));
+
+        let mut slabs = Vec::with_capacity(xs.len().saturating_sub(1));
+        let mut active: Vec<usize> = Vec::new();
+        let mut next = 0;
+        let mut inside = vec![false; labels.len()];
+        for i in 0..xs.len().saturating_sub(1) {
+            while next < by_start.len() && edges[by_start[next]].0.x <= xs[i] {
+                active.push(by_start[next]);
+                next += 1;
+            }
+            active.retain(|&e| edges[e].1.x > xs[i]);
+
+            let middle = (xs[i] + xs[i + 1]) / 2.0;
+            let mut slab_edges = active.clone();
+            slab_edges
+                .sort_by(|&a, &b| y_at(&edges[a], middle).total_cmp(&y_at(&edges[b], middle)));
+
+            // walk upwards and keep track of the regions we are currently in
+            let mut entered: Vec<usize> = Vec::new();
+            let mut regions = Vec::with_capacity(slab_edges.len());
+            for &e in &slab_edges {
+                let region = edges[e].2;
+                inside[region] = !inside[region];
+                if inside[region] {
+                    entered.push(region);
+                } else {
+                    entered.retain(|&r| r != region);
+                }
+                regions.push(entered.last().copied());
+            }
+            for region in entered {
+                inside[region] = false;
+            }
+            slabs.push(Slab {
+                edges: slab_edges,
+                regions,
+            });
+        }
+
+        Self {
+            labels,
+            edges,
+            xs,
+            slabs,
+        }
+    }
+
+    pub fn from_polygons(regions: impl IntoIterator<Item = (L, Vec<Polygon>)>) -> Self {
+        Self::new(regions.into_iter().map(|(label, polygons)| {
+            let rings = polygons
+                .iter()
+                .flat_map(|polygon| polygon.rings().cloned())
+                .collect();
+            (label, rings)
+        }))
+    }
+
+    pub fn labels(&self) -> &[L] {
+        &self.labels
+    }
+
+    pub fn into_labels(self) -> Vec<L> {
+        self.labels
+    }
+
+    // Index of the region containing `point` (into `labels()`). A point on a border belongs to
+    // the region right of or below it, so neighbouring regions never share a point.
+    pub fn locate_index(&self, point: &Point) -> Option<usize> {
+        let slab = self.xs.partition_point(|&x| x <= point.x);
+        if slab == 0 || slab >= self.xs.len() {
+            return None;
+        }
+        let slab = &self.slabs[slab - 1];
+        let below = slab
+            .edges
+            .partition_point(|&e| y_at(&self.edges[e], point.x) < point.y);
+        if below == 0 {
+            return None;
+        }
+        slab.regions[below - 1]
+    }
+
+    pub fn locate(&self, point: &Point) -> Option<&L> {
+        self.locate_index(point).map(|region| &self.labels[region])
+    }
+
+    // Spatial join: the region for every point.
+    pub fn locate_all<'a>(&'a self, points: &[Point]) -> Vec<Option<&'a L>> {
+        points.iter().map(|point| self.locate(point)).collect()
+    }
+}
+
+fn y_at(edge: &(Point, Point, usize), x: f64) -> f64 {
+    let (a, b) = (edge.0, edge.1);
+    a.y + (x - a.x) / (b.x - a.x) * (b.y - a.y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(x1: f64, y1: f64, x2: f64, y2: f64) -> Vec<Point> {
+        [(x1, y1), (x2, y1), (x2, y2), (x1, y2), (x1, y1)]
+            .iter()
+            .map(|&(x, y)| Point { x, y })
+            .collect()
+    }
+
+    fn p(x: f64, y: f64) -> Point {
+        Point { x, y }
+    }
+
+    #[test]
+    fn hole_by_even_odd_rule() {
+        let slabs = SlabDecomposition::new([(
+            "ring",
+            vec![square(0.0, 0.0, 4.0, 4.0), square(1.0, 1.0, 3.0, 3.0)],
+        )]);
+        assert_eq!(slabs.locate(&p(0.5, 2.0)), Some(&"ring"));
+        assert_eq!(slabs.locate(&p(2.0, 3.5)), Some(&"ring"));
+        assert_eq!(slabs.locate(&p(2.0, 2.0)), None);
+    }
+
+    #[test]
+    fn points_on_slab_boundaries_and_vertices() {
+        // four cells meeting in (2, 2)
+        let cells = [
+            ("south west", square(0.0, 0.0, 2.0, 2.0)),
+            ("south east", square(2.0, 0.0, 4.0, 2.0)),
+            ("north west", square(0.0, 2.0, 2.0, 4.0)),
+            ("north east", square(2.0, 2.0, 4.0, 4.0)),
+        ];
+        let slabs = SlabDecomposition::new(cells.map(|(label, ring)| (label, vec![ring])));
+        // on the slab boundary x = 2
+        assert_eq!(slabs.locate(&p(2.0, 1.0)), Some(&"south east"));
+        assert_eq!(slabs.locate(&p(2.0, 3.0)), Some(&"north east"));
+        // on a horizontal border
+        assert_eq!(slabs.locate(&p(1.0, 2.0)), Some(&"south west"));
+        // vertices
+        assert_eq!(slabs.locate(&p(2.0, 2.0)), Some(&"south east"));
+        assert_eq!(slabs.locate(&p(0.0, 4.0)), Some(&"north west"));
+        assert_eq!(slabs.locate(&p(0.0, 0.0)), None);
+        assert_eq!(slabs.locate(&p(4.0, 4.0)), None);
+    }
+
+    #[test]
+    fn innermost_of_nested_regions() {
+        // neither region is cut out of the one around it
+        let slabs = SlabDecomposition::new([
+            ("country", vec![square(0.0, 0.0, 10.0, 10.0)]),
+            ("state", vec![square(2.0, 2.0, 8.0, 8.0)]),
+            ("city", vec![square(4.0, 4.0, 6.0, 6.0)]),
+        ]);
+        let points = [
+            p(1.0, 5.0),
+            p(3.0, 5.0),
+            p(5.0, 5.0),
+            p(7.0, 5.0),
+            p(9.0, 5.0),
+        ];
+        assert_eq!(
+            slabs.locate_all(&points),
+            vec![
+                Some(&"country"),
+                Some(&"state"),
+                Some(&"city"),
+                Some(&"state"),
+                Some(&"country")
+            ]
+        );
+    }
+
+    #[test]
+    fn outside_of_all_regions() {
+        let slabs = SlabDecomposition::new([
+            ("left", vec![square(0.0, 0.0, 2.0, 2.0)]),
+            ("right", vec![square(4.0, 1.0, 6.0, 3.0)]),
+        ]);
+        for point in [
+            p(-1.0, 1.0),
+            p(7.0, 1.0),
+            p(1.0, -1.0),
+            p(1.0, 3.0),
+            // between both regions and below the right one
+            p(3.0, 1.5),
+            p(5.0, 0.5),
+        ] {
+            assert_eq!(slabs.locate_index(&point), None, "{:?}", point);
+        }
+        assert_eq!(slabs.locate_index(&p(5.0, 2.0)), Some(1));
+    }
+
+    #[test]
+    fn non_finite_coordinates() {
+        let mut broken = square(1.0, 1.0, 3.0, 3.0);
+        broken[2] = p(f64::NAN, f64::NAN);
+        let slabs = SlabDecomposition::new([
+            ("square", vec![square(0.0, 0.0, 4.0, 4.0)]),
+            (
+                "broken",
+                vec![broken, vec![p(f64::INFINITY, 0.0), p(1.0, 1.0)]],
+            ),
+        ]);
+        assert_eq!(slabs.locate_index(&p(2.0, 2.0)), Some(0));
+        assert_eq!(slabs.locate_index(&p(f64::NAN, 2.0)), None);
+        assert_eq!(slabs.locate_index(&p(2.0, f64::NAN)), None);
+    }
+}
@@ -0,0 +1,128 @@
+use crate::lib::{
+    half_plane::{HalfPlane, HalfPlaneIntersection, intersect_half_planes},
+    line::Line,
+    point::Point,
+    triangulation::normalized_ring,
+};
+
+// A polygon with optional holes. Every ring is stored closed (first point == last point),
+// which is the same representation `read_polygon_from_file` returns.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Polygon {
+    pub exterior: Vec<Point>,
+    pub holes: Vec<Vec<Point>>,
+}
+
+pub type MultiPolygon = Vec<Polygon>;
+
+impl Polygon {
+    pub fn new(exterior: Vec<Point>) -> Self {
+        Self {
+            exterior: close_ring(exterior),
+            holes: Vec::new(),
+        }
+    }
+
+    pub fn with_holes(exterior: Vec<Point>, holes: Vec<Vec<Point>>) -> Self {
+        Self {
+            exterior: close_ring(exterior),
+            holes: holes.into_iter().map(close_ring).collect(),
+        }
+    }
+
+    pub fn rings(&self) -> impl Iterator<Item = &Vec<Point>> {
+        std::iter::once(&self.exterior).chain(self.holes.iter())
+    }
+
+    pub fn area(&self) -> f64 {
+        ring_signed_area(&self.exterior).abs()
+            - self
+                .holes
+                .iter()
+                .map(|hole| ring_signed_area(hole).abs())
+                .sum::<f64>()
+    }
+
+    pub fn contains(&self, point: &Point) -> bool {
+        ring_contains(&self.exterior, point) && !self.holes.iter().any(|h| ring_contains(h, point))
+    }
+
+    pub fn edges(&self) -> Vec<Line> {
+        self.rings()
+            .flat_map(|ring| ring.windows(2).map(|pair| Line::new(pair[0], pair[1])))
+            .collect()
+    }
+
+    // All points that see the whole polygon: the intersection of the inner half-planes of all
+    // edges. Polygons with holes never have a kernel.
+    pub fn kernel(&self) -> Option<Polygon> {
+        if !self.holes.is_empty() {
+            return None;
+        }
+        let ring = normalized_ring(&self.exterior);
+        let half_planes: Vec<HalfPlane> = (0..ring.len())
+            .map(|i| HalfPlane::through(ring[i], ring[(i + 1) % ring.len()]))
+            .collect();
+        match intersect_half_planes(&half_planes) {
+            HalfPlaneIntersection::Bounded(kernel) => Some(kernel),
+            _ => None,
+        }
+    }
+
+    pub fn is_star_shaped(&self) -> bool {
+        self.kernel().is_some()
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        // the closing point of every ring is not counted
+        self.rings().map(|ring| ring.len().saturating_sub(1)).sum()
+    }
+}
+
+pub fn close_ring(mut ring: Vec<Point>) -> Vec<Point> {
+    if let (Some(first), Some(last)) = (ring.first(), ring.last()) {
+        if first != last {
+            ring.push(*first);
+        }
+    }
+    ring
+}
+
+// Shoelace formula, positive for counter-clockwise rings.
+pub fn ring_signed_area(ring: &[Point]) -> f64 {
+    let mut area = 0.0;
+    for pair in ring.windows(2) {
+        area += pair[0].x * pair[1].y - pair[1].x * pair[0].y;
+    }
+    if let (Some(first), Some(last)) = (ring.first(), ring.last()) {
+        if first != last {
+            area += last.x * first.y - first.x * last.y;
+        }
+    }
+    area / 2.0
+}
+
+pub fn is_ccw(ring: &[Point]) -> bool {
+    ring_signed_area(ring) > 0.0
+}
+
+// Even-odd test with a horizontal ray. Unlike `Point::is_in_polygon` this does not need
+// a reference point outside of the polygon.
+pub fn ring_contains(ring: &[Point], point: &Point) -> bool {
+    let mut inside = false;
+    let n = ring.len();
+    if n < 3 {
+        return false;
+    }
+    let mut j = n - 1;
+    for i in 0..n {
+        let (a, b) = (ring[i], ring[j]);
+        if (a.y > point.y) != (b.y > point.y)
+            && point.x < (b.x - a.x) * (point.y - a.y) / (b.y - a.y) + a.x
+        {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
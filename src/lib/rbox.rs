@@ -0,0 +1,203 @@
+use crate::lib::{
+    generator::Rng,
+    mesh::{PointCloud, write_rbox},
+};
+use std::{error::Error, f64::consts::PI, fmt};
+
+// Native versions of the rbox distributions used in p04. Like in rbox, the cube, diamond, sphere
+// and polygon are centered at the origin with "radius" 0.5, the simplex is spanned by the origin
+// and the unit vectors.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Distribution {
+    // 'c': random points in the cube plus its 2^d corners
+    UnitCube,
+    // 'd': random points in the cube plus the 2d vertices of the diamond
+    UnitDiamond,
+    // 's': random points on the sphere
+    Cospherical,
+    // 'x': random points in the simplex spanned by the origin and the unit vectors
+    Simplex,
+    // 'y': like 'x', plus the d + 1 vertices of the simplex
+    SimplexWithVertices,
+    // 'r': regular polygon in the first two coordinates
+    RegularPolygon,
+}
+
+impl Distribution {
+    pub fn from_flag(flag: char) -> Option<Self> {
+        match flag {
+            'c' => Some(Distribution::UnitCube),
+            'd' => Some(Distribution::UnitDiamond),
+            's' => Some(Distribution::Cospherical),
+            'x' => Some(Distribution::Simplex),
+            'y' => Some(Distribution::SimplexWithVertices),
+            'r' => Some(Distribution::RegularPolygon),
+            _ => None,
+        }
+    }
+
+    pub fn flag(&self) -> char {
+        match self {
+            Distribution::UnitCube => 'c',
+            Distribution::UnitDiamond => 'd',
+            Distribution::Cospherical => 's',
+            Distribution::Simplex => 'x',
+            Distribution::SimplexWithVertices => 'y',
+            Distribution::RegularPolygon => 'r',
+        }
+    }
+}
+
+// The points can not be generated, e.g. the 2^d corners of a cube with d >= 64.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RboxError {
+    pub reason: String,
+}
+
+impl fmt::Display for RboxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.reason)
+    }
+}
+
+impl Error for RboxError {}
+
+// `count` generated points plus the extra vertices of the distribution (for 'c', 'd', 'y').
+pub fn rbox(
+    distribution: Distribution,
+    count: usize,
+    dimension: usize,
+    seed: u64,
+) -> Result<PointCloud, RboxError> {
+    let corners = match distribution {
+        Distribution::UnitCube => u32::try_from(dimension)
+            .ok()
+            .and_then(|dimension| 1usize.checked_shl(dimension))
+            .ok_or_else(|| RboxError {
+                reason: format!("a cube in {} dimensions has too many corners", dimension),
+            })?,
+        _ => 0,
+    };
+    let mut rng = Rng::new(seed);
+    let mut points = PointCloud::new(dimension);
+    let mut point = vec![0.0; dimension];
+
+    for i in 0..count {
+        match distribution {
+            Distribution::UnitCube | Distribution::UnitDiamond => {
+                point.iter_mut().for_each(|x| *x = rng.range(-0.5, 0.5));
+            }
+            Distribution::Cospherical => {
+                // normalized gaussian vectors are uniform on the sphere
+                let mut length = 0.0;
+                while length == 0.0 {
+                    point.iter_mut().for_each(|x| *x = rng.normal());
+                    length = point.iter().map(|x| x * x).sum::<f64>().sqrt();
+                }
+                point.iter_mut().for_each(|x| *x *= 0.5 / length);
+            }
+            Distribution::Simplex | Distribution::SimplexWithVertices => {
+                // the gaps between sorted uniform numbers are uniform on the simplex
+                let mut cuts: Vec<f64> = (0..dimension).map(|_| rng.next_f64()).collect();
+                cuts.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                let mut previous = 0.0;
+                for (x, cut) in point.iter_mut().zip(cuts) {
+                    *x = cut - previous;
+                    previous = cut;
+                }
+            }
+            Distribution::RegularPolygon => {
+                let angle = 2.0 * PI * i as f64 / count as f64;
+                point.iter_mut().for_each(|x| *x = 0.0);
+                if let Some(x) = point.get_mut(0) {
+                    *x = 0.5 * angle.cos();
+                }
+                if let Some(y) = point.get_mut(1) {
+                    *y = 0.5 * angle.sin();
+                }
+            }
+        }
+        points.push(&point);
+    }
+
+    match distribution {
+        Distribution::UnitCube => {
+            for corner in 0..corners {
+                let vertex: Vec<f64> = (0..dimension)
+                    .map(|k| if corner >> k & 1 == 1 { 0.5 } else { -0.5 })
+                    .collect();
+                points.push(&vertex);
+            }
+        }
+        Distribution::UnitDiamond => {
+            for k in 0..dimension {
+                for sign in [-0.5, 0.5] {
+                    let mut vertex = vec![0.0; dimension];
+                    vertex[k] = sign;
+                    points.push(&vertex);
+                }
+            }
+        }
+        Distribution::SimplexWithVertices => {
+            points.push(&vec![0.0; dimension]);
+            for k in 0..dimension {
+                let mut vertex = vec![0.0; dimension];
+                vertex[k] = 1.0;
+                points.push(&vertex);
+            }
+        }
+        _ => {}
+    }
+    Ok(points)
+}
+
+// The same text rbox prints for "rbox <count> D<dimension> <flag>", i.e. qhull input.
+pub fn rbox_text(
+    distribution: Distribution,
+    count: usize,
+    dimension: usize,
+    seed: u64,
+) -> Result<String, RboxError> {
+    Ok(write_rbox(
+        &rbox(distribution, count, dimension, seed)?,
+        &format!("rbox {} D{} {}", count, dimension, distribution.flag()),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lib::mesh::parse_rbox;
+
+    #[test]
+    fn distributions() {
+        let cube = rbox(Distribution::UnitCube, 10, 3, 1).unwrap();
+        assert_eq!(cube.len(), 10 + 8);
+        assert!(cube.coordinates.iter().all(|x| x.abs() <= 0.5));
+
+        let sphere = rbox(Distribution::Cospherical, 10, 4, 1).unwrap();
+        for p in sphere.points() {
+            assert!((p.iter().map(|x| x * x).sum::<f64>().sqrt() - 0.5).abs() < 1e-12);
+        }
+
+        let simplex = rbox(Distribution::SimplexWithVertices, 10, 5, 1).unwrap();
+        assert_eq!(simplex.len(), 10 + 6);
+        assert!(
+            simplex
+                .points()
+                .all(|p| p.iter().all(|&x| x >= 0.0) && p.iter().sum::<f64>() <= 1.0)
+        );
+
+        assert_eq!(rbox(Distribution::UnitDiamond, 0, 6, 1).unwrap().len(), 12);
+        // only the cube has 2^d extra points
+        assert_eq!(
+            rbox(Distribution::UnitDiamond, 1, 64, 1).unwrap().len(),
+            129
+        );
+        assert!(rbox(Distribution::UnitCube, 1, 64, 1).is_err());
+
+        let text = rbox_text(Distribution::RegularPolygon, 6, 2, 1).unwrap();
+        assert!(text.starts_with("2 rbox 6 D2 r\n6\n"));
+        assert_eq!(parse_rbox(&text, "rbox").unwrap().len(), 6);
+    }
+}
@@ -0,0 +1,136 @@
+use crate::lib::{
+    line::Line,
+    point::Point,
+    sweep::{Colours, Intersection, visit_coloured_intersections},
+    sweep_status::BTreeStatus,
+};
+use std::ops::ControlFlow;
+
+// An intersection between the two layers, e.g. roads and borders or two versions of a map.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RedBlueIntersection {
+    pub point: Point,
+    // index in the red segments
+    pub red: usize,
+    // index in the blue segments
+    pub blue: usize,
+}
+
+// Every pair of a red and a blue segment for which `Line::crosses` holds, once. Crossings
+// within one colour are not reported.
+pub fn red_blue_intersections(red: &[Line], blue: &[Line]) -> Vec<RedBlueIntersection> {
+    coloured(red, blue, false)
+}
+
+// The same for colours that do not cross themselves, like the edges of a planar map: segments
+// of one colour may only share endpoints, touch or overlap. The sweep then never has to look
+// for crossings between neighbours of the same colour. If a colour does cross itself, red-blue
+// crossings can be missed.
+pub fn red_blue_intersections_non_crossing(
+    red: &[Line],
+    blue: &[Line],
+) -> Vec<RedBlueIntersection> {
+    coloured(red, blue, true)
+}
+
+fn coloured(red: &[Line], blue: &[Line], non_crossing: bool) -> Vec<RedBlueIntersection> {
+    let colours = Colours {
+        red: red.len(),
+        non_crossing,
+    };
+    let mut result = Vec::new();
+    let _ = visit_coloured_intersections::<BTreeStatus, ()>(
+        red.iter().chain(blue).cloned(),
+        colours,
+        |intersection: &Intersection| {
+            // the red index is the smaller one
+            let (r, b) = intersection.segments;
+            result.push(RedBlueIntersection {
+                point: intersection.point,
+                red: r,
+                blue: b - red.len(),
+            });
+            ControlFlow::Continue(())
+        },
+    );
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lib::generator::SegmentGenerator;
+
+    fn pairs(found: &[RedBlueIntersection]) -> Vec<(usize, usize)> {
+        let mut pairs: Vec<_> = found.iter().map(|i| (i.red, i.blue)).collect();
+        pairs.sort_unstable();
+        pairs
+    }
+
+    fn brute_force(red: &[Line], blue: &[Line]) -> Vec<(usize, usize)> {
+        let mut pairs = Vec::new();
+        for (i, r) in red.iter().enumerate() {
+            for (j, b) in blue.iter().enumerate() {
+                if r.crosses(b) {
+                    pairs.push((i, j));
+                }
+            }
+        }
+        pairs
+    }
+
+    // the edges of a square grid, rotated around the origin
+    fn lattice(cells: usize, size: f64, angle: f64, offset: Point) -> Vec<Line> {
+        let (sin, cos) = angle.sin_cos();
+        let at = |i: usize, j: usize| {
+            let (x, y) = (i as f64 * size, j as f64 * size);
+            Point {
+                x: x * cos - y * sin + offset.x,
+                y: x * sin + y * cos + offset.y,
+            }
+        };
+        let mut lines = Vec::new();
+        for i in 0..=cells {
+            for j in 0..=cells {
+                if i < cells {
+                    lines.push(Line::new(at(i, j), at(i + 1, j)));
+                }
+                if j < cells {
+                    lines.push(Line::new(at(i, j), at(i, j + 1)));
+                }
+            }
+        }
+        lines
+    }
+
+    #[test]
+    fn only_crossings_between_colours() {
+        let mut generator =
+            SegmentGenerator::new(2, Point { x: 0.0, y: 0.0 }, Point { x: 100.0, y: 100.0 });
+        let red = generator.uniform(300, 20.0);
+        let blue = generator.grid_snapped(300, 20.0, 5.0);
+        let found = red_blue_intersections(&red, &blue);
+        assert_eq!(pairs(&found), brute_force(&red, &blue));
+        for intersection in &found {
+            let line = &red[intersection.red];
+            assert!(line.point_distance(intersection.point).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn non_crossing_colours() {
+        let red = lattice(8, 10.0, 0.0, Point { x: 0.0, y: 0.0 });
+        let blue = lattice(6, 12.0, 0.5, Point { x: 30.0, y: -10.0 });
+        // a copy of the red grid touches and overlaps it everywhere
+        let shifted = lattice(8, 10.0, 0.0, Point { x: 5.0, y: 10.0 });
+        for blue in [blue, shifted] {
+            let expected = brute_force(&red, &blue);
+            assert!(!expected.is_empty());
+            assert_eq!(
+                pairs(&red_blue_intersections_non_crossing(&red, &blue)),
+                expected
+            );
+            assert_eq!(pairs(&red_blue_intersections(&red, &blue)), expected);
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use crate::lib::{
+    data::{DataError, numbers_to_line, parse_row},
+    line::Line,
+};
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+};
+
+// Reads segments row by row from any buffered reader. Only one row is in memory at a time and
+// its buffer is reused, so the memory use does not depend on the file size.
+pub struct SegmentReader<R> {
+    reader: R,
+    buffer: String,
+    line: usize,
+    path: String,
+}
+
+impl SegmentReader<BufReader<File>> {
+    pub fn open(path: &str) -> Result<Self, DataError> {
+        let file = File::open(path).map_err(|e| io_error(path, e))?;
+        Ok(Self::new(BufReader::with_capacity(1 << 16, file), path))
+    }
+}
+
+impl<R: BufRead> SegmentReader<R> {
+    // `path` is only used for error messages.
+    pub fn new(reader: R, path: &str) -> Self {
+        Self {
+            reader,
+            buffer: String::new(),
+            line: 0,
+            path: path.to_string(),
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for SegmentReader<R> {
+    type Item = Result<Line, DataError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.buffer.clear();
+            match self.reader.read_line(&mut self.buffer) {
+                Ok(0) => return None,
+                Ok(_) => {
+                    self.line += 1;
+                    match parse_row(&self.buffer, self.line, &self.path) {
+                        Ok(Some(numbers)) => return Some(Ok(numbers_to_line(numbers))),
+                        Ok(None) => continue,
+                        Err(e) => return Some(Err(e)),
+                    }
+                }
+                Err(e) => {
+                    let mut error = io_error(&self.path, e);
+                    error.line = self.line + 1;
+                    return Some(Err(error));
+                }
+            }
+        }
+    }
+}
+
+// The whole file mapped into memory. The rows are parsed straight from the mapping, so the
+// file content is never copied. It is checked to be UTF-8 once when opening.
+#[cfg(feature = "mmap")]
+pub struct MappedSegments {
+    map: memmap2::Mmap,
+    path: String,
+}
+
+#[cfg(feature = "mmap")]
+impl MappedSegments {
+    pub fn open(path: &str) -> Result<Self, DataError> {
+        let file = File::open(path).map_err(|e| io_error(path, e))?;
+        // SAFETY: the file must not be changed by another process while it is mapped, which
+        // is fine for our read-only input files.
+        let map = unsafe { memmap2::Mmap::map(&file) }.map_err(|e| io_error(path, e))?;
+        std::str::from_utf8(&map).map_err(|e| io_error(path, e))?;
+        Ok(Self {
+            map,
+            path: path.to_string(),
+        })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Result<Line, DataError>> + '_ {
+        // SAFETY: checked in `open`, and the mapping is read-only
+        let text = unsafe { std::str::from_utf8_unchecked(&self.map) };
+        crate::lib::data::segments(text, &self.path)
+    }
+}
+
+fn io_error(path: &str, error: impl std::fmt::Display) -> DataError {
+    DataError {
+        path: path.to_string(),
+        line: 0,
+        column: 0,
+        reason: error.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lib::point::Point;
+    use std::io::Cursor;
+
+    #[test]
+    fn reads_rows_lazily() {
+        let text = "1 2 3 4\n\n# comment\n5 6 7 8\n1 2 x 4\n";
+        let mut reader = SegmentReader::new(Cursor::new(text), "test.dat");
+        assert_eq!(reader.next().unwrap().unwrap().p1, Point { x: 1.0, y: 2.0 });
+        assert_eq!(reader.next().unwrap().unwrap().p2, Point { x: 7.0, y: 8.0 });
+        let error = reader.next().unwrap().unwrap_err();
+        assert_eq!((error.line, error.column), (5, 5));
+        assert!(reader.next().is_none());
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn mapped_file() {
+        let path = std::env::temp_dir().join("cg_ss_25_mapped.dat");
+        let path = path.to_str().unwrap();
+        std::fs::write(path, "1 2 3 4\n# comment\n5 6 7 8\n").unwrap();
+        let segments = MappedSegments::open(path).unwrap();
+        let lines: Vec<Line> = segments.iter().collect::<Result<_, _>>().unwrap();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1].p2, Point { x: 7.0, y: 8.0 });
+        // twice, without opening it again
+        assert_eq!(segments.iter().count(), 2);
+        drop(segments);
+
+        std::fs::write(path, b"1 2 3 \xff\n").unwrap();
+        assert!(MappedSegments::open(path).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,119 @@
+use crate::lib::{
+    ccw::ccw,
+    line::Line,
+    point::Point,
+    polygon::Polygon,
+    sweep::{Intersection, visit_intersections},
+};
+use std::ops::ControlFlow;
+
+// Shamos-Hoey: the sweep of p03 stops at the first intersection, so finding none takes
+// O(n log n).
+
+// The first pair of segments for which `Line::crosses` holds, in sweep order.
+pub fn any_intersection(lines: impl IntoIterator<Item = Line>) -> Option<Intersection> {
+    first_intersection(lines, |_, _| false)
+}
+
+// For a closed ring (first point == last point). Segment i is the edge from ring[i] to
+// ring[i + 1]; consecutive edges touching only at their common vertex are no intersection.
+pub fn ring_self_intersection(ring: &[Point]) -> Option<Intersection> {
+    polygon_self_intersection(&Polygon::new(ring.to_vec()))
+}
+
+// The segments are indices into `polygon.edges()`, edges of different rings always count.
+pub fn polygon_self_intersection(polygon: &Polygon) -> Option<Intersection> {
+    let edges = polygon.edges();
+    // index of the following edge in the same ring
+    let mut next = Vec::with_capacity(edges.len());
+    for ring in polygon.rings() {
+        let first = next.len();
+        let count = ring.len().saturating_sub(1);
+        next.extend((1..=count).map(|k| first + k % count));
+    }
+    let adjacent = |a: usize, b: usize| {
+        let (a, b) = if next[a] == b { (a, b) } else { (b, a) };
+        // touching at more than the common vertex if the edges fold back onto each other
+        next[a] == b && !overlap(&edges[a], &edges[b])
+    };
+    first_intersection(edges.iter().cloned(), adjacent)
+}
+
+pub fn is_simple(polygon: &Polygon) -> bool {
+    polygon_self_intersection(polygon).is_none()
+}
+
+fn first_intersection(
+    lines: impl IntoIterator<Item = Line>,
+    ignore: impl Fn(usize, usize) -> bool,
+) -> Option<Intersection> {
+    match visit_intersections(lines, |intersection| {
+        let (a, b) = intersection.segments;
+        if ignore(a, b) {
+            ControlFlow::Continue(())
+        } else {
+            ControlFlow::Break(*intersection)
+        }
+    }) {
+        ControlFlow::Break(intersection) => Some(intersection),
+        ControlFlow::Continue(()) => None,
+    }
+}
+
+// `b` starts where `a` ends, do they share more than that point?
+fn overlap(a: &Line, b: &Line) -> bool {
+    let (p, q, r) = (a.p1, a.p2, b.p2);
+    ccw(&p, &q, &r) == 0 && (p.x - q.x) * (r.x - q.x) + (p.y - q.y) * (r.y - q.y) > 0.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ring(points: &[(f64, f64)]) -> Vec<Point> {
+        points.iter().map(|&(x, y)| Point { x, y }).collect()
+    }
+
+    #[test]
+    fn finds_first_crossing() {
+        let line = |x1, y1, x2, y2| Line::new(Point { x: x1, y: y1 }, Point { x: x2, y: y2 });
+        assert_eq!(
+            any_intersection(vec![line(0.0, 0.0, 1.0, 0.0), line(0.0, 1.0, 1.0, 1.0)]),
+            None
+        );
+        let found = any_intersection(vec![
+            line(5.0, 0.0, 7.0, 2.0),
+            line(0.0, 0.0, 2.0, 2.0),
+            line(5.0, 2.0, 7.0, 0.0),
+            line(0.0, 2.0, 2.0, 0.0),
+        ]);
+        assert_eq!(found.map(|i| i.segments), Some((1, 3)));
+    }
+
+    #[test]
+    fn rings() {
+        let square = ring(&[(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0)]);
+        assert_eq!(ring_self_intersection(&square), None);
+
+        let bowtie = ring(&[(0.0, 0.0), (4.0, 4.0), (4.0, 0.0), (0.0, 4.0), (0.0, 0.0)]);
+        let found = ring_self_intersection(&bowtie).unwrap();
+        assert_eq!(found.segments, (0, 2));
+        assert_eq!(found.point, Point { x: 2.0, y: 2.0 });
+
+        // every edge folds back onto the previous one
+        let spike = ring(&[(0.0, 0.0), (4.0, 0.0), (2.0, 0.0)]);
+        assert!(ring_self_intersection(&spike).is_some());
+
+        // a vertex touching an edge that is not adjacent
+        let touching = ring(&[(0.0, 0.0), (4.0, 0.0), (2.0, 2.0), (4.0, 4.0), (2.0, 0.0)]);
+        assert!(ring_self_intersection(&touching).is_some());
+
+        let hole = ring(&[(1.0, 1.0), (2.0, 1.0), (2.0, 2.0)]);
+        assert!(is_simple(&Polygon::with_holes(square.clone(), vec![hole])));
+        let crossing_hole = ring(&[(3.0, 3.0), (5.0, 3.0), (5.0, 5.0)]);
+        assert!(!is_simple(&Polygon::with_holes(
+            square,
+            vec![crossing_hole]
+        )));
+    }
+}
@@ -0,0 +1,700 @@
+use crate::lib::{
+    ccw::ccw,
+    line::Line,
+    point::Point,
+    polygon::{Polygon, close_ring, ring_contains},
+};
+use ordered_float::NotNan;
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Method {
+    // tolerance = maximal distance of a removed vertex to the simplified polyline
+    DouglasPeucker,
+    // tolerance = minimal (effective) triangle area a vertex needs to be kept
+    VisvalingamWhyatt,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimplificationReport {
+    pub vertices_before: usize,
+    pub vertices_after: usize,
+    pub area_before: f64,
+    pub area_after: f64,
+}
+
+impl SimplificationReport {
+    pub fn new(before: &Polygon, after: &Polygon) -> Self {
+        Self {
+            vertices_before: before.vertex_count(),
+            vertices_after: after.vertex_count(),
+            area_before: before.area(),
+            area_after: after.area(),
+        }
+    }
+
+    pub fn area_change(&self) -> f64 {
+        self.area_after - self.area_before
+    }
+
+    pub fn relative_area_change(&self) -> f64 {
+        if self.area_before == 0.0 {
+            0.0
+        } else {
+            self.area_change() / self.area_before
+        }
+    }
+}
+
+pub fn simplify_polyline(points: &[Point], tolerance: f64, method: Method) -> Vec<Point> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+    let mut keep = vec![true; points.len()];
+    simplify_range(
+        points,
+        0,
+        points.len() - 1,
+        tolerance,
+        method,
+        &mut keep,
+        &mut |_, _, _| true,
+    );
+    kept_points(points, &keep)
+}
+
+// Simplifies a closed ring. The first vertex and the vertex farthest away from it are kept,
+// rings that would degenerate to less than three vertices are returned unchanged.
+pub fn simplify_ring(ring: &[Point], tolerance: f64, method: Method) -> Vec<Point> {
+    let ring = close_ring(ring.to_vec());
+    if ring.len() < 5 {
+        return ring;
+    }
+    let last = ring.len() - 1;
+    let far = (1..last)
+        .max_by(|&a, &b| {
+            ring[0]
+                .distance(&ring[a])
+                .partial_cmp(&ring[0].distance(&ring[b]))
+                .unwrap()
+        })
+        .unwrap();
+
+    let mut keep = vec![true; ring.len()];
+    let mut always_valid = |_: usize, _: usize, _: &[bool]| true;
+    simplify_range(
+        &ring,
+        0,
+        far,
+        tolerance,
+        method,
+        &mut keep,
+        &mut always_valid,
+    );
+    simplify_range(
+        &ring,
+        far,
+        last,
+        tolerance,
+        method,
+        &mut keep,
+        &mut always_valid,
+    );
+
+    let simplified = kept_points(&ring, &keep);
+    if simplified.len() < 4 {
+        ring
+    } else {
+        simplified
+    }
+}
+
+pub fn simplify_polygon(polygon: &Polygon, tolerance: f64, method: Method) -> Polygon {
+    Polygon {
+        exterior: simplify_ring(&polygon.exterior, tolerance, method),
+        holes: polygon
+            .holes
+            .iter()
+            .map(|hole| simplify_ring(hole, tolerance, method))
+            .collect(),
+    }
+}
+
+// Simplifies a set of polygons that may share borders (e.g. neighbouring states).
+//
+// Every ring is cut into chains at vertices where more than two rings meet (or where a ring
+// has less than three such vertices, at evenly spaced vertices). Each distinct chain is
+// simplified exactly once, so shared borders stay identical and no gaps appear. A vertex is
+// only removed if the shortcut neither crosses the current geometry of any chain nor moves
+// another vertex to the other side of the border, both are looked up in a grid of the current
+// segments.
+pub fn simplify_topology(
+    polygons: &[Polygon],
+    tolerance: f64,
+    method: Method,
+) -> (Vec<Polygon>, Vec<SimplificationReport>) {
+    // open rings, the closing point is added again at the end
+    let rings: Vec<Vec<Point>> = polygons
+        .iter()
+        .flat_map(|polygon| polygon.rings())
+        .map(|ring| open_ring(ring))
+        .collect();
+
+    let nodes = find_nodes(&rings);
+    let mut chains: Vec<Vec<Point>> = Vec::new();
+    let mut chain_ids: HashMap<Vec<PointKey>, usize> = HashMap::new();
+    // per ring: the chains it consists of, and whether they have to be reversed
+    let mut ring_chains: Vec<Option<Vec<(usize, bool)>>> = Vec::new();
+
+    for ring in &rings {
+        if ring.len() < 3 {
+            ring_chains.push(None);
+            continue;
+        }
+        let node_positions: Vec<usize> = (0..ring.len())
+            .filter(|&i| nodes.contains(&key(&ring[i])))
+            .collect();
+        let mut parts = Vec::new();
+        for (n, &start) in node_positions.iter().enumerate() {
+            let end = node_positions[(n + 1) % node_positions.len()];
+            let mut chain = vec![ring[start]];
+            let mut i = start;
+            loop {
+                i = (i + 1) % ring.len();
+                chain.push(ring[i]);
+                if i == end {
+                    break;
+                }
+            }
+            let forward: Vec<PointKey> = chain.iter().map(key).collect();
+            let backward: Vec<PointKey> = forward.iter().rev().copied().collect();
+            let reversed = backward < forward;
+            let canonical = if reversed { backward } else { forward };
+            let id = *chain_ids.entry(canonical).or_insert_with(|| {
+                if reversed {
+                    chain.reverse();
+                }
+                chains.push(chain);
+                chains.len() - 1
+            });
+            parts.push((id, reversed));
+        }
+        ring_chains.push(Some(parts));
+    }
+
+    let mut keeps: Vec<Vec<bool>> = chains.iter().map(|chain| vec![true; chain.len()]).collect();
+    let mut grid = SegmentGrid::new(&chains);
+    for c in 0..chains.len() {
+        let last = chains[c].len() - 1;
+        simplify_range(
+            &chains[c],
+            0,
+            last,
+            tolerance,
+            method,
+            &mut keeps[c],
+            &mut |i, j, own: &[bool]| {
+                let valid = is_valid_shortcut(&chains, &grid, c, own, i, j);
+                if valid {
+                    grid.replace(&chains, c, own, i, j);
+                }
+                valid
+            },
+        );
+    }
+
+    let mut simplified_rings = rings
+        .iter()
+        .zip(&ring_chains)
+        .map(|(ring, parts)| match parts {
+            None => close_ring(ring.clone()),
+            Some(parts) => {
+                let mut points: Vec<Point> = Vec::new();
+                for &(id, reversed) in parts {
+                    let mut chain = kept_points(&chains[id], &keeps[id]);
+                    if reversed {
+                        chain.reverse();
+                    }
+                    if !points.is_empty() {
+                        chain.remove(0);
+                    }
+                    points.extend(chain);
+                }
+                close_ring(points)
+            }
+        });
+
+    let mut result = Vec::new();
+    let mut reports = Vec::new();
+    for polygon in polygons {
+        let simplified = Polygon {
+            exterior: simplified_rings.next().unwrap(),
+            holes: (0..polygon.holes.len())
+                .map(|_| simplified_rings.next().unwrap())
+                .collect(),
+        };
+        reports.push(SimplificationReport::new(polygon, &simplified));
+        result.push(simplified);
+    }
+    (result, reports)
+}
+
+type PointKey = (u64, u64);
+
+fn key(point: &Point) -> PointKey {
+    (point.x.to_bits(), point.y.to_bits())
+}
+
+fn open_ring(ring: &[Point]) -> Vec<Point> {
+    let mut ring = ring.to_vec();
+    if ring.len() > 1 && ring.first() == ring.last() {
+        ring.pop();
+    }
+    ring
+}
+
+// Vertices with other than two distinct neighbours are junctions between rings.
+fn find_nodes(rings: &[Vec<Point>]) -> HashSet<PointKey> {
+    let mut neighbours: HashMap<PointKey, HashSet<PointKey>> = HashMap::new();
+    for ring in rings.iter().filter(|ring| ring.len() >= 3) {
+        for i in 0..ring.len() {
+            let entry = neighbours.entry(key(&ring[i])).or_default();
+            entry.insert(key(&ring[(i + 1) % ring.len()]));
+            entry.insert(key(&ring[(i + ring.len() - 1) % ring.len()]));
+        }
+    }
+    let mut nodes: HashSet<PointKey> = neighbours
+        .into_iter()
+        .filter(|(_, n)| n.len() != 2)
+        .map(|(k, _)| k)
+        .collect();
+
+    // every ring needs at least three fixed vertices so that it can not collapse
+    for ring in rings.iter().filter(|ring| ring.len() >= 3) {
+        let count = ring.iter().filter(|p| nodes.contains(&key(p))).count();
+        if count < 3 {
+            for k in 0..3 {
+                nodes.insert(key(&ring[k * ring.len() / 3]));
+            }
+        }
+    }
+    nodes
+}
+
+fn kept_points(points: &[Point], keep: &[bool]) -> Vec<Point> {
+    points
+        .iter()
+        .zip(keep)
+        .filter(|(_, k)| **k)
+        .map(|(p, _)| *p)
+        .collect()
+}
+
+fn triangle_area(a: &Point, b: &Point, c: &Point) -> f64 {
+    ((b.x - a.x) * (c.y - a.y) - (c.x - a.x) * (b.y - a.y)).abs() / 2.0
+}
+
+// Removes vertices strictly between `first` and `last` by clearing their `keep` flag.
+// `valid(i, j, keep)` decides whether the kept vertices between i and j may be replaced by
+// the segment (i, j), they are removed whenever it returns true.
+fn simplify_range(
+    points: &[Point],
+    first: usize,
+    last: usize,
+    tolerance: f64,
+    method: Method,
+    keep: &mut [bool],
+    valid: &mut impl FnMut(usize, usize, &[bool]) -> bool,
+) {
+    if last <= first + 1 {
+        return;
+    }
+    match method {
+        Method::DouglasPeucker => douglas_peucker(points, first, last, tolerance, keep, valid),
+        Method::VisvalingamWhyatt => {
+            visvalingam_whyatt(points, first, last, tolerance, keep, valid)
+        }
+    }
+}
+
+fn douglas_peucker(
+    points: &[Point],
+    first: usize,
+    last: usize,
+    tolerance: f64,
+    keep: &mut [bool],
+    valid: &mut impl FnMut(usize, usize, &[bool]) -> bool,
+) {
+    let mut stack = vec![(first, last)];
+    while let Some((i, j)) = stack.pop() {
+        if j <= i + 1 {
+            continue;
+        }
+        let (k, max_distance) = (i + 1..j)
+            .map(|k| (k, points[k].distance_to_segment(&points[i], &points[j])))
+            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+            .unwrap();
+        if max_distance <= tolerance && valid(i, j, keep) {
+            keep[i + 1..j].iter_mut().for_each(|k| *k = false);
+        } else {
+            stack.push((k, j));
+            stack.push((i, k));
+        }
+    }
+}
+
+fn visvalingam_whyatt(
+    points: &[Point],
+    first: usize,
+    last: usize,
+    tolerance: f64,
+    keep: &mut [bool],
+    valid: &mut impl FnMut(usize, usize, &[bool]) -> bool,
+) {
+    let mut prev: Vec<usize> = (0..points.len()).map(|i| i.saturating_sub(1)).collect();
+    let mut next: Vec<usize> = (0..points.len()).map(|i| i + 1).collect();
+    let mut areas = vec![f64::INFINITY; points.len()];
+    let mut heap = BinaryHeap::new();
+
+    for i in first + 1..last {
+        areas[i] = triangle_area(&points[i - 1], &points[i], &points[i + 1]);
+        heap.push(Reverse((NotNan::new(areas[i]).unwrap(), i)));
+    }
+
+    while let Some(Reverse((area, i))) = heap.pop() {
+        let area = area.into_inner();
+        if !keep[i] || area != areas[i] {
+            // outdated heap entry
+            continue;
+        }
+        if area >= tolerance {
+            break;
+        }
+        let (p, n) = (prev[i], next[i]);
+        if !valid(p, n, keep) {
+            // blocked until one of its neighbours is removed
+            areas[i] = f64::INFINITY;
+            continue;
+        }
+        keep[i] = false;
+        next[p] = n;
+        prev[n] = p;
+
+        // the effective area of a neighbour never drops below the area of the removed vertex
+        for neighbour in [p, n] {
+            if neighbour == first || neighbour == last {
+                continue;
+            }
+            let new_area = triangle_area(
+                &points[prev[neighbour]],
+                &points[neighbour],
+                &points[next[neighbour]],
+            )
+            .max(area);
+            areas[neighbour] = new_area;
+            heap.push(Reverse((NotNan::new(new_area).unwrap(), neighbour)));
+        }
+    }
+}
+
+// Two segments are in conflict if they intersect anywhere but in a shared endpoint.
+fn segments_conflict(a: &Point, b: &Point, p: &Point, q: &Point) -> bool {
+    if !Line::new(*a, *b).crosses(&Line::new(*p, *q)) {
+        return false;
+    }
+    let shared = [(a, b, p, q), (a, b, q, p), (b, a, p, q), (b, a, q, p)]
+        .into_iter()
+        .find(|(s, _, t, _)| s == t);
+    match shared {
+        Some((s, other1, _, other2)) => {
+            // touching in the shared endpoint is fine, overlapping is not
+            ccw(a, b, p) == 0
+                && ccw(a, b, q) == 0
+                && (other1.x - s.x) * (other2.x - s.x) + (other1.y - s.y) * (other2.y - s.y) > 0.0
+        }
+        None => true,
+    }
+}
+
+fn is_valid_shortcut(
+    chains: &[Vec<Point>],
+    grid: &SegmentGrid,
+    chain: usize,
+    own_keep: &[bool],
+    i: usize,
+    j: usize,
+) -> bool {
+    let (a, b) = (chains[chain][i], chains[chain][j]);
+    if a == b {
+        return false;
+    }
+    let region: Vec<Point> = (i..=j)
+        .filter(|&k| own_keep[k])
+        .map(|k| chains[chain][k])
+        .collect();
+    let (min_x, max_x, min_y, max_y) = region.iter().fold(
+        (
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+        ),
+        |(x0, x1, y0, y1), p| (x0.min(p.x), x1.max(p.x), y0.min(p.y), y1.max(p.y)),
+    );
+    let in_box = |p: &Point| p.x >= min_x && p.x <= max_x && p.y >= min_y && p.y <= max_y;
+
+    // every kept vertex is the endpoint of a current segment in its cell
+    for (c, first, last) in grid.segments_near(min_x, max_x, min_y, max_y) {
+        let points = &chains[c];
+        if !(c == chain && first >= i && last <= j) {
+            let (p, q) = (&points[first], &points[last]);
+            if p.x.max(q.x) >= min_x
+                && p.x.min(q.x) <= max_x
+                && p.y.max(q.y) >= min_y
+                && p.y.min(q.y) <= max_y
+                && segments_conflict(&a, &b, p, q)
+            {
+                return false;
+            }
+        }
+        for k in [first, last] {
+            if c == chain && k >= i && k <= j {
+                continue;
+            }
+            let p = &points[k];
+            if *p != a && *p != b && in_box(p) && ring_contains(&region, p) {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+// chain, first vertex, last vertex
+type Segment = (usize, usize, usize);
+type Cell = (i64, i64);
+
+// Uniform grid over the current segments of all chains, every segment is in all cells its
+// bounding box overlaps.
+struct SegmentGrid {
+    origin: (f64, f64),
+    cell_size: f64,
+    cells: HashMap<Cell, Vec<Segment>>,
+}
+
+impl SegmentGrid {
+    // About one segment per cell.
+    fn new(chains: &[Vec<Point>]) -> Self {
+        let (min_x, max_x, min_y, max_y) = chains.iter().flatten().fold(
+            (
+                f64::INFINITY,
+                f64::NEG_INFINITY,
+                f64::INFINITY,
+                f64::NEG_INFINITY,
+            ),
+            |(x0, x1, y0, y1), p| (x0.min(p.x), x1.max(p.x), y0.min(p.y), y1.max(p.y)),
+        );
+        let count: usize = chains.iter().map(|chain| chain.len() - 1).sum();
+        let cell_size = (max_x - min_x).max(max_y - min_y) / (count as f64).sqrt().max(1.0);
+        let mut grid = Self {
+            origin: (min_x, min_y),
+            // a single cell if all points are the same or the extent overflows
+            cell_size: if cell_size > 0.0 && cell_size.is_finite() {
+                cell_size
+            } else {
+                f64::INFINITY
+            },
+            cells: HashMap::new(),
+        };
+        for (c, chain) in chains.iter().enumerate() {
+            for k in 1..chain.len() {
+                let cells = grid.cells_of(&chain[k - 1], &chain[k]);
+                grid.insert(cells, (c, k - 1, k));
+            }
+        }
+        grid
+    }
+
+    fn cell(&self, x: f64, y: f64) -> Cell {
+        (
+            ((x - self.origin.0) / self.cell_size).floor() as i64,
+            ((y - self.origin.1) / self.cell_size).floor() as i64,
+        )
+    }
+
+    fn cells_of(&self, p: &Point, q: &Point) -> (Cell, Cell) {
+        (
+            self.cell(p.x.min(q.x), p.y.min(q.y)),
+            self.cell(p.x.max(q.x), p.y.max(q.y)),
+        )
+    }
+
+    fn insert(&mut self, (low, high): (Cell, Cell), segment: Segment) {
+        for x in low.0..=high.0 {
+            for y in low.1..=high.1 {
+                self.cells.entry((x, y)).or_default().push(segment);
+            }
+        }
+    }
+
+    fn remove(&mut self, (low, high): (Cell, Cell), segment: Segment) {
+        for x in low.0..=high.0 {
+            for y in low.1..=high.1 {
+                if let Some(segments) = self.cells.get_mut(&(x, y)) {
+                    segments.retain(|s| *s != segment);
+                }
+            }
+        }
+    }
+
+    // Replaces the kept vertices between i and j of `chain` by the segment (i, j).
+    fn replace(&mut self, chains: &[Vec<Point>], chain: usize, keep: &[bool], i: usize, j: usize) {
+        let points = &chains[chain];
+        let kept: Vec<usize> = (i..=j).filter(|&k| keep[k]).collect();
+        for pair in kept.windows(2) {
+            let cells = self.cells_of(&points[pair[0]], &points[pair[1]]);
+            self.remove(cells, (chain, pair[0], pair[1]));
+        }
+        let cells = self.cells_of(&points[i], &points[j]);
+        self.insert(cells, (chain, i, j));
+    }
+
+    fn segments_near(&self, min_x: f64, max_x: f64, min_y: f64, max_y: f64) -> Vec<Segment> {
+        let (low, high) = (self.cell(min_x, min_y), self.cell(max_x, max_y));
+        let mut segments: Vec<Segment> = (low.0..=high.0)
+            .flat_map(|x| (low.1..=high.1).map(move |y| (x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .collect();
+        segments.sort_unstable();
+        segments.dedup();
+        segments
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn p(x: f64, y: f64) -> Point {
+        Point { x, y }
+    }
+
+    fn corner() -> Vec<Point> {
+        vec![
+            p(0.0, 0.0),
+            p(1.0, 0.01),
+            p(2.0, 0.0),
+            p(2.0, 1.0),
+            p(2.0, 2.0),
+        ]
+    }
+
+    #[test]
+    fn douglas_peucker() {
+        let simplified = simplify_polyline(&corner(), 0.1, Method::DouglasPeucker);
+        assert_eq!(simplified, vec![p(0.0, 0.0), p(2.0, 0.0), p(2.0, 2.0)]);
+        // below the tolerance nothing moves
+        let simplified = simplify_polyline(&corner(), 0.001, Method::DouglasPeucker);
+        assert_eq!(
+            simplified,
+            vec![p(0.0, 0.0), p(1.0, 0.01), p(2.0, 0.0), p(2.0, 2.0)]
+        );
+    }
+
+    #[test]
+    fn visvalingam_whyatt() {
+        let simplified = simplify_polyline(&corner(), 0.1, Method::VisvalingamWhyatt);
+        assert_eq!(simplified, vec![p(0.0, 0.0), p(2.0, 0.0), p(2.0, 2.0)]);
+        let simplified = simplify_polyline(&corner(), 3.0, Method::VisvalingamWhyatt);
+        assert_eq!(simplified, vec![p(0.0, 0.0), p(2.0, 2.0)]);
+    }
+
+    #[test]
+    fn blocked_vertex_is_evaluated_again() {
+        // vertex 2 has the smallest area, but may not be cut off between 1 and 3
+        let points = [
+            p(0.0, 0.0),
+            p(1.0, 0.2),
+            p(2.0, 0.1),
+            p(3.0, 0.3),
+            p(4.0, 0.0),
+        ];
+        let mut keep = vec![true; points.len()];
+        simplify_range(
+            &points,
+            0,
+            4,
+            10.0,
+            Method::VisvalingamWhyatt,
+            &mut keep,
+            &mut |i, j, _: &[bool]| (i, j) != (1, 3),
+        );
+        assert_eq!(keep, vec![true, false, false, false, true]);
+    }
+
+    #[test]
+    fn shortcut_sees_the_current_segments() {
+        // a peak above the tip of a long spike
+        let chains = vec![
+            vec![p(0.0, 1.0), p(1.0, 2.0), p(2.0, 1.0), p(3.0, 1.0)],
+            vec![p(1.0, -100.0), p(1.0, 1.5), p(1.5, -100.0)],
+        ];
+        let mut grid = SegmentGrid::new(&chains);
+        assert!(!is_valid_shortcut(&chains, &grid, 0, &[true; 4], 0, 2));
+
+        // without the tip of the spike the peak can be cut off
+        assert!(is_valid_shortcut(&chains, &grid, 1, &[true; 3], 0, 2));
+        grid.replace(&chains, 1, &[true; 3], 0, 2);
+        assert!(is_valid_shortcut(&chains, &grid, 0, &[true; 4], 0, 2));
+    }
+
+    #[test]
+    fn shared_border_is_simplified_once() {
+        // the border between both squares zigzags through (1.99, 1)
+        let border = [p(2.01, 0.5), p(1.99, 1.0), p(2.01, 1.5)];
+        let mut west = vec![p(0.0, 0.0), p(2.0, 0.0)];
+        west.extend(border);
+        west.extend([p(2.0, 2.0), p(0.0, 2.0)]);
+        let mut east = vec![p(2.0, 0.0), p(4.0, 0.0), p(4.0, 2.0), p(2.0, 2.0)];
+        east.extend(border.iter().rev());
+        let polygons = [Polygon::new(west), Polygon::new(east)];
+
+        let (simplified, reports) = simplify_topology(&polygons, 0.1, Method::DouglasPeucker);
+        let on_border = |polygon: &Polygon| {
+            let mut points: Vec<Point> = open_ring(&polygon.exterior)
+                .into_iter()
+                .filter(|q| (q.x - 2.0).abs() < 0.1)
+                .collect();
+            points.sort_by(|a, b| a.y.total_cmp(&b.y));
+            points
+        };
+        assert_eq!(on_border(&simplified[0]), on_border(&simplified[1]));
+        assert!(!on_border(&simplified[0]).contains(&p(1.99, 1.0)));
+
+        // what one square loses, the other one gains
+        assert_eq!(
+            (reports[0].vertices_before, reports[0].vertices_after),
+            (7, 6)
+        );
+        assert_eq!(
+            (reports[1].vertices_before, reports[1].vertices_after),
+            (7, 6)
+        );
+        assert!((reports[0].area_change() - 0.01).abs() < 1e-9);
+        assert!((reports[1].area_change() + 0.01).abs() < 1e-9);
+        assert!((reports[0].relative_area_change() - 0.01 / reports[0].area_before).abs() < 1e-12);
+    }
+
+    #[test]
+    fn report_of_an_empty_polygon() {
+        let empty = Polygon::new(Vec::new());
+        let report = SimplificationReport::new(&empty, &empty);
+        assert_eq!(report.vertices_before, 0);
+        assert_eq!(report.relative_area_change(), 0.0);
+    }
+}
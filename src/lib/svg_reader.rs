@@ -0,0 +1,934 @@
+use crate::lib::{point::Point, polygon::close_ring};
+use std::{collections::HashMap, error::Error, f64::consts::PI, fmt, fs::read_to_string};
+
+// Maximal distance between a flattened curve and the real curve, in user units.
+pub const DEFAULT_TOLERANCE: f64 = 0.01;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvgError {
+    // line in the document (1-based), 0 if unknown
+    pub line: usize,
+    pub reason: String,
+}
+
+impl fmt::Display for SvgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
+    }
+}
+
+impl Error for SvgError {}
+
+fn error(reason: impl Into<String>) -> SvgError {
+    SvgError {
+        line: 0,
+        reason: reason.into(),
+    }
+}
+
+// Affine transform, x' = a*x + c*y + e, y' = b*x + d*y + f (same order as SVG's matrix()).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+    pub e: f64,
+    pub f: f64,
+}
+
+impl Transform {
+    pub const IDENTITY: Transform = Transform {
+        a: 1.0,
+        b: 0.0,
+        c: 0.0,
+        d: 1.0,
+        e: 0.0,
+        f: 0.0,
+    };
+
+    pub fn apply(&self, p: &Point) -> Point {
+        Point {
+            x: self.a * p.x + self.c * p.y + self.e,
+            y: self.b * p.x + self.d * p.y + self.f,
+        }
+    }
+
+    // self * other => `other` is applied first
+    pub fn then(&self, other: &Transform) -> Transform {
+        Transform {
+            a: self.a * other.a + self.c * other.b,
+            b: self.b * other.a + self.d * other.b,
+            c: self.a * other.c + self.c * other.d,
+            d: self.b * other.c + self.d * other.d,
+            e: self.a * other.e + self.c * other.f + self.e,
+            f: self.b * other.e + self.d * other.f + self.f,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Subpath {
+    pub points: Vec<Point>,
+    pub closed: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SvgPath {
+    pub id: Option<String>,
+    // in document coordinates, i.e. with all transforms applied
+    pub subpaths: Vec<Subpath>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SvgDocument {
+    pub paths: Vec<SvgPath>,
+    // centers of circles, ellipses and Inkscape arcs (sodipodi:type="arc")
+    pub points: Vec<(Option<String>, Point)>,
+}
+
+impl SvgDocument {
+    pub fn from_file(path: &str) -> Result<Self, SvgError> {
+        let text = read_to_string(path).map_err(|e| error(format!("{}: {}", path, e)))?;
+        Self::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, SvgError> {
+        Self::parse_with_tolerance(text, DEFAULT_TOLERANCE)
+    }
+
+    pub fn parse_with_tolerance(text: &str, tolerance: f64) -> Result<Self, SvgError> {
+        check_tolerance(tolerance)?;
+        let mut document = SvgDocument::default();
+        // transform of every open element
+        let mut stack: Vec<(String, Transform)> = Vec::new();
+
+        for tag in Tags::new(text) {
+            let tag = tag?;
+            let with_line = |e: SvgError| SvgError {
+                line: tag.line,
+                ..e
+            };
+            match tag.kind {
+                TagKind::End => {
+                    if let Some(position) = stack.iter().rposition(|(name, _)| *name == tag.name) {
+                        stack.truncate(position);
+                    }
+                }
+                TagKind::Start | TagKind::Empty => {
+                    let parent = stack.last().map_or(Transform::IDENTITY, |(_, t)| *t);
+                    let transform = match tag.attributes.get("transform") {
+                        Some(value) => parent.then(&parse_transform(value).map_err(with_line)?),
+                        None => parent,
+                    };
+                    document
+                        .add_element(&tag, &transform, tolerance)
+                        .map_err(with_line)?;
+                    if tag.kind == TagKind::Start {
+                        stack.push((tag.name.clone(), transform));
+                    }
+                }
+            }
+        }
+        Ok(document)
+    }
+
+    // Every path with an id as a set of closed rings, e.g. the states of a map.
+    pub fn labeled_polygons(&self) -> Vec<(String, Vec<Vec<Point>>)> {
+        self.paths
+            .iter()
+            .filter_map(|path| {
+                let id = path.id.clone()?;
+                let rings = path
+                    .subpaths
+                    .iter()
+                    .filter(|subpath| subpath.points.len() >= 3)
+                    .map(|subpath| close_ring(subpath.points.clone()))
+                    .collect();
+                Some((id, rings))
+            })
+            .collect()
+    }
+
+    pub fn labeled_points(&self) -> Vec<(String, Point)> {
+        self.points
+            .iter()
+            .filter_map(|(id, point)| Some((id.clone()?, *point)))
+            .collect()
+    }
+
+    fn add_element(
+        &mut self,
+        tag: &Tag,
+        transform: &Transform,
+        tolerance: f64,
+    ) -> Result<(), SvgError> {
+        let id = tag.attributes.get("id").cloned();
+        let number = |name: &str| -> Result<f64, SvgError> {
+            match tag.attributes.get(name) {
+                Some(value) => parse_number_list(value)?
+                    .first()
+                    .copied()
+                    .ok_or_else(|| error(format!("attribute {} is empty", name))),
+                None => Ok(0.0),
+            }
+        };
+
+        let subpaths = match tag.name.as_str() {
+            "path" if tag.attributes.get("sodipodi:type").map(String::as_str) == Some("arc") => {
+                let center = Point {
+                    x: number("sodipodi:cx")?,
+                    y: number("sodipodi:cy")?,
+                };
+                self.points.push((id, transform.apply(&center)));
+                return Ok(());
+            }
+            "circle" | "ellipse" => {
+                let center = Point {
+                    x: number("cx")?,
+                    y: number("cy")?,
+                };
+                self.points.push((id, transform.apply(&center)));
+                return Ok(());
+            }
+            "path" => parse_path_data(
+                tag.attributes.get("d").map_or("", String::as_str),
+                tolerance,
+            )?,
+            "polygon" | "polyline" => {
+                let numbers =
+                    parse_number_list(tag.attributes.get("points").map_or("", String::as_str))?;
+                vec![Subpath {
+                    points: numbers
+                        .chunks_exact(2)
+                        .map(|pair| Point {
+                            x: pair[0],
+                            y: pair[1],
+                        })
+                        .collect(),
+                    closed: tag.name == "polygon",
+                }]
+            }
+            "rect" => {
+                let (x, y) = (number("x")?, number("y")?);
+                let (width, height) = (number("width")?, number("height")?);
+                vec![Subpath {
+                    points: vec![
+                        Point { x, y },
+                        Point { x: x + width, y },
+                        Point {
+                            x: x + width,
+                            y: y + height,
+                        },
+                        Point { x, y: y + height },
+                    ],
+                    closed: true,
+                }]
+            }
+            _ => return Ok(()),
+        };
+
+        self.paths.push(SvgPath {
+            id,
+            subpaths: subpaths
+                .into_iter()
+                .map(|subpath| Subpath {
+                    points: subpath.points.iter().map(|p| transform.apply(p)).collect(),
+                    closed: subpath.closed,
+                })
+                .collect(),
+        });
+        Ok(())
+    }
+}
+
+// Parses the `transform` attribute, e.g. "translate(10,20) rotate(45 5 5) scale(2)".
+pub fn parse_transform(text: &str) -> Result<Transform, SvgError> {
+    let mut result = Transform::IDENTITY;
+    let mut rest = text.trim();
+    while !rest.is_empty() {
+        let open = rest
+            .find('(')
+            .ok_or_else(|| error(format!("invalid transform '{}'", text)))?;
+        let close = rest
+            .find(')')
+            .ok_or_else(|| error(format!("missing ')' in transform '{}'", text)))?;
+        let name = rest[..open].trim().trim_start_matches(',').trim();
+        let values = parse_number_list(&rest[open + 1..close])?;
+        let value = |i: usize| values.get(i).copied();
+        let arity_error = || error(format!("wrong number of values for {}()", name));
+
+        let transform = match (name, values.len()) {
+            ("matrix", 6) => Transform {
+                a: values[0],
+                b: values[1],
+                c: values[2],
+                d: values[3],
+                e: values[4],
+                f: values[5],
+            },
+            ("translate", 1 | 2) => Transform {
+                e: values[0],
+                f: value(1).unwrap_or(0.0),
+                ..Transform::IDENTITY
+            },
+            ("scale", 1 | 2) => Transform {
+                a: values[0],
+                d: value(1).unwrap_or(values[0]),
+                ..Transform::IDENTITY
+            },
+            ("rotate", 1 | 3) => {
+                let (sin, cos) = values[0].to_radians().sin_cos();
+                let rotation = Transform {
+                    a: cos,
+                    b: sin,
+                    c: -sin,
+                    d: cos,
+                    ..Transform::IDENTITY
+                };
+                let (cx, cy) = (value(1).unwrap_or(0.0), value(2).unwrap_or(0.0));
+                let to_center = Transform {
+                    e: cx,
+                    f: cy,
+                    ..Transform::IDENTITY
+                };
+                let from_center = Transform {
+                    e: -cx,
+                    f: -cy,
+                    ..Transform::IDENTITY
+                };
+                to_center.then(&rotation).then(&from_center)
+            }
+            ("skewX", 1) => Transform {
+                c: values[0].to_radians().tan(),
+                ..Transform::IDENTITY
+            },
+            ("skewY", 1) => Transform {
+                b: values[0].to_radians().tan(),
+                ..Transform::IDENTITY
+            },
+            ("matrix" | "translate" | "scale" | "rotate" | "skewX" | "skewY", _) => {
+                return Err(arity_error());
+            }
+            _ => return Err(error(format!("unknown transform '{}'", name))),
+        };
+        result = result.then(&transform);
+        rest = rest[close + 1..].trim_start();
+    }
+    Ok(result)
+}
+
+// Parses SVG path data with all commands (absolute and relative). Curves and arcs are
+// flattened so that the polyline stays within `tolerance` of the curve.
+pub fn parse_path_data(data: &str, tolerance: f64) -> Result<Vec<Subpath>, SvgError> {
+    check_tolerance(tolerance)?;
+    let mut scanner = Scanner::new(data);
+    let mut subpaths: Vec<Subpath> = Vec::new();
+    let mut points: Vec<Point> = Vec::new();
+    let mut current = Point { x: 0.0, y: 0.0 };
+    let mut start = current;
+    // reflected control point for S/s and T/t
+    let mut last_cubic: Option<Point> = None;
+    let mut last_quadratic: Option<Point> = None;
+    let mut command: Option<char> = None;
+
+    let finish = |points: &mut Vec<Point>, subpaths: &mut Vec<Subpath>, closed: bool| {
+        if points.len() > 1 {
+            subpaths.push(Subpath {
+                points: std::mem::take(points),
+                closed,
+            });
+        }
+        points.clear();
+    };
+
+    loop {
+        scanner.skip_separators();
+        if scanner.at_end() {
+            break;
+        }
+        if let Some(letter) = scanner.command() {
+            command = Some(letter);
+        } else {
+            // implicit repetition, a moveto is followed by implicit linetos
+            command = match command {
+                Some('M') => Some('L'),
+                Some('m') => Some('l'),
+                Some('Z' | 'z') | None => {
+                    return Err(error(format!("expected a command in path '{}'", data)));
+                }
+                other => other,
+            };
+        }
+        let letter = command.unwrap();
+        let relative = letter.is_ascii_lowercase();
+        let origin = if relative {
+            current
+        } else {
+            Point { x: 0.0, y: 0.0 }
+        };
+        let read_point = |scanner: &mut Scanner| -> Result<Point, SvgError> {
+            Ok(Point {
+                x: scanner.number()? + origin.x,
+                y: scanner.number()? + origin.y,
+            })
+        };
+
+        if points.is_empty() && !matches!(letter, 'M' | 'm') {
+            // drawing after a closepath starts at the start of the previous subpath
+            points.push(current);
+        }
+
+        let mut cubic_control = None;
+        let mut quadratic_control = None;
+        match letter.to_ascii_uppercase() {
+            'M' => {
+                finish(&mut points, &mut subpaths, false);
+                current = read_point(&mut scanner)?;
+                start = current;
+                points.push(current);
+            }
+            'L' => {
+                current = read_point(&mut scanner)?;
+                points.push(current);
+            }
+            'H' => {
+                current.x = scanner.number()? + origin.x;
+                points.push(current);
+            }
+            'V' => {
+                current.y = scanner.number()? + origin.y;
+                points.push(current);
+            }
+            'C' | 'S' => {
+                let control1 = if letter.eq_ignore_ascii_case(&'C') {
+                    read_point(&mut scanner)?
+                } else {
+                    last_cubic.map_or(current, |c| current + (current - c))
+                };
+                let control2 = read_point(&mut scanner)?;
+                let end = read_point(&mut scanner)?;
+                flatten_cubic(current, control1, control2, end, tolerance, 0, &mut points);
+                cubic_control = Some(control2);
+                current = end;
+            }
+            'Q' | 'T' => {
+                let control = if letter.eq_ignore_ascii_case(&'Q') {
+                    read_point(&mut scanner)?
+                } else {
+                    last_quadratic.map_or(current, |c| current + (current - c))
+                };
+                let end = read_point(&mut scanner)?;
+                // degree elevation to a cubic curve
+                let control1 = Point {
+                    x: current.x + 2.0 / 3.0 * (control.x - current.x),
+                    y: current.y + 2.0 / 3.0 * (control.y - current.y),
+                };
+                let control2 = Point {
+                    x: end.x + 2.0 / 3.0 * (control.x - end.x),
+                    y: end.y + 2.0 / 3.0 * (control.y - end.y),
+                };
+                flatten_cubic(current, control1, control2, end, tolerance, 0, &mut points);
+                quadratic_control = Some(control);
+                current = end;
+            }
+            'A' => {
+                let rx = scanner.number()?;
+                let ry = scanner.number()?;
+                let rotation = scanner.number()?;
+                let large_arc = scanner.flag()?;
+                let sweep = scanner.flag()?;
+                let end = read_point(&mut scanner)?;
+                flatten_arc(
+                    current,
+                    rx,
+                    ry,
+                    rotation,
+                    large_arc,
+                    sweep,
+                    end,
+                    tolerance,
+                    &mut points,
+                );
+                current = end;
+            }
+            'Z' => {
+                finish(&mut points, &mut subpaths, true);
+                current = start;
+            }
+            _ => return Err(error(format!("unknown path command '{}'", letter))),
+        }
+        last_cubic = cubic_control;
+        last_quadratic = quadratic_control;
+    }
+    finish(&mut points, &mut subpaths, false);
+    Ok(subpaths)
+}
+
+fn parse_number_list(text: &str) -> Result<Vec<f64>, SvgError> {
+    let mut scanner = Scanner::new(text);
+    let mut numbers = Vec::new();
+    loop {
+        scanner.skip_separators();
+        if scanner.at_end() {
+            return Ok(numbers);
+        }
+        numbers.push(scanner.number()?);
+    }
+}
+
+fn check_tolerance(tolerance: f64) -> Result<(), SvgError> {
+    if tolerance > 0.0 && tolerance.is_finite() {
+        Ok(())
+    } else {
+        Err(error(format!("invalid tolerance {}", tolerance)))
+    }
+}
+
+fn flatten_cubic(
+    p0: Point,
+    p1: Point,
+    p2: Point,
+    p3: Point,
+    tolerance: f64,
+    depth: u32,
+    points: &mut Vec<Point>,
+) {
+    let flat = p1.distance_to_segment(&p0, &p3) <= tolerance
+        && p2.distance_to_segment(&p0, &p3) <= tolerance;
+    if flat || depth >= 16 {
+        points.push(p3);
+        return;
+    }
+    // de Casteljau subdivision at t = 0.5
+    let middle = |a: Point, b: Point| Point {
+        x: (a.x + b.x) / 2.0,
+        y: (a.y + b.y) / 2.0,
+    };
+    let p01 = middle(p0, p1);
+    let p12 = middle(p1, p2);
+    let p23 = middle(p2, p3);
+    let p012 = middle(p01, p12);
+    let p123 = middle(p12, p23);
+    let split = middle(p012, p123);
+    flatten_cubic(p0, p01, p012, split, tolerance, depth + 1, points);
+    flatten_cubic(split, p123, p23, p3, tolerance, depth + 1, points);
+}
+
+// Elliptical arc in endpoint parametrization, converted to center parametrization as described
+// in the SVG specification (implementation notes, F.6.5).
+#[allow(clippy::too_many_arguments)]
+fn flatten_arc(
+    from: Point,
+    rx: f64,
+    ry: f64,
+    rotation: f64,
+    large_arc: bool,
+    sweep: bool,
+    to: Point,
+    tolerance: f64,
+    points: &mut Vec<Point>,
+) {
+    let (mut rx, mut ry) = (rx.abs(), ry.abs());
+    if from == to {
+        return;
+    }
+    if rx == 0.0 || ry == 0.0 {
+        points.push(to);
+        return;
+    }
+    let (sin, cos) = rotation.to_radians().sin_cos();
+    let dx = (from.x - to.x) / 2.0;
+    let dy = (from.y - to.y) / 2.0;
+    let x1 = cos * dx + sin * dy;
+    let y1 = -sin * dx + cos * dy;
+
+    let lambda = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+    if lambda > 1.0 {
+        rx *= lambda.sqrt();
+        ry *= lambda.sqrt();
+    }
+    let numerator = rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1;
+    let denominator = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+    let sign = if large_arc == sweep { -1.0 } else { 1.0 };
+    let coefficient = sign * (numerator / denominator).max(0.0).sqrt();
+    let cx1 = coefficient * rx * y1 / ry;
+    let cy1 = -coefficient * ry * x1 / rx;
+    let cx = cos * cx1 - sin * cy1 + (from.x + to.x) / 2.0;
+    let cy = sin * cx1 + cos * cy1 + (from.y + to.y) / 2.0;
+
+    let angle = |ux: f64, uy: f64, vx: f64, vy: f64| (ux * vy - uy * vx).atan2(ux * vx + uy * vy);
+    let start_angle = angle(1.0, 0.0, (x1 - cx1) / rx, (y1 - cy1) / ry);
+    let mut delta = angle(
+        (x1 - cx1) / rx,
+        (y1 - cy1) / ry,
+        (-x1 - cx1) / rx,
+        (-y1 - cy1) / ry,
+    );
+    if !sweep && delta > 0.0 {
+        delta -= 2.0 * PI;
+    } else if sweep && delta < 0.0 {
+        delta += 2.0 * PI;
+    }
+
+    // largest angle step whose chord stays within the tolerance
+    let radius = rx.max(ry);
+    let step = if tolerance >= radius {
+        PI / 2.0
+    } else {
+        (2.0 * (1.0 - tolerance / radius).acos()).min(PI / 2.0)
+    };
+    // for huge radii the step rounds to 0, so cap like the depth limit of `flatten_cubic`
+    let segments = ((delta.abs() / step).ceil() as usize).clamp(1, 1 << 16);
+    for i in 1..segments {
+        let t = start_angle + delta * i as f64 / segments as f64;
+        points.push(Point {
+            x: cx + rx * cos * t.cos() - ry * sin * t.sin(),
+            y: cy + rx * sin * t.cos() + ry * cos * t.sin(),
+        });
+    }
+    points.push(to);
+}
+
+struct Scanner<'a> {
+    text: &'a str,
+    position: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(text: &'a str) -> Self {
+        Self { text, position: 0 }
+    }
+
+    fn at_end(&self) -> bool {
+        self.position >= self.text.len()
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.position).copied()
+    }
+
+    fn skip_separators(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r' | b',')) {
+            self.position += 1;
+        }
+    }
+
+    fn command(&mut self) -> Option<char> {
+        match self.peek() {
+            Some(c) if c.is_ascii_alphabetic() && c != b'e' && c != b'E' => {
+                self.position += 1;
+                Some(c as char)
+            }
+            _ => None,
+        }
+    }
+
+    // Arc flags may be written without separators ("a1 1 0 01 1 1").
+    fn flag(&mut self) -> Result<bool, SvgError> {
+        self.skip_separators();
+        let flag = match self.peek() {
+            Some(b'0') => false,
+            Some(b'1') => true,
+            _ => return Err(error(format!("expected a flag in '{}'", self.text))),
+        };
+        self.position += 1;
+        Ok(flag)
+    }
+
+    // Numbers may follow each other without separators ("1.5.5-2" = 1.5, .5, -2).
+    fn number(&mut self) -> Result<f64, SvgError> {
+        self.skip_separators();
+        let bytes = self.text.as_bytes();
+        let begin = self.position;
+        let mut end = begin;
+        if matches!(bytes.get(end), Some(b'+' | b'-')) {
+            end += 1;
+        }
+        let mut seen_dot = false;
+        while let Some(&c) = bytes.get(end) {
+            if c.is_ascii_digit() || (c == b'.' && !seen_dot) {
+                seen_dot |= c == b'.';
+                end += 1;
+            } else {
+                break;
+            }
+        }
+        if matches!(bytes.get(end), Some(b'e' | b'E')) {
+            let mut exponent_end = end + 1;
+            if matches!(bytes.get(exponent_end), Some(b'+' | b'-')) {
+                exponent_end += 1;
+            }
+            if bytes.get(exponent_end).is_some_and(u8::is_ascii_digit) {
+                end = exponent_end;
+                while bytes.get(end).is_some_and(u8::is_ascii_digit) {
+                    end += 1;
+                }
+            }
+        }
+        let number = self.text[begin..end].parse::<f64>().map_err(|_| {
+            error(format!(
+                "expected a number at '{}'",
+                self.text[begin..].chars().take(20).collect::<String>()
+            ))
+        })?;
+        if !number.is_finite() {
+            return Err(error(format!(
+                "number out of range at '{}'",
+                &self.text[begin..end]
+            )));
+        }
+        self.position = end;
+        Ok(number)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum TagKind {
+    Start,
+    End,
+    // <tag ... />
+    Empty,
+}
+
+struct Tag {
+    name: String,
+    kind: TagKind,
+    attributes: HashMap<String, String>,
+    line: usize,
+}
+
+// Minimal XML tokenizer: returns start, end and empty element tags and skips comments,
+// processing instructions, doctype and CDATA sections.
+struct Tags<'a> {
+    text: &'a str,
+    position: usize,
+    line: usize,
+}
+
+impl<'a> Tags<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            text,
+            position: 0,
+            line: 1,
+        }
+    }
+
+    fn advance_to(&mut self, position: usize) {
+        self.line += self.text[self.position..position].matches('\n').count();
+        self.position = position;
+    }
+
+    fn skip_past(&mut self, terminator: &str) -> Result<(), SvgError> {
+        match self.text[self.position..].find(terminator) {
+            Some(offset) => {
+                self.advance_to(self.position + offset + terminator.len());
+                Ok(())
+            }
+            None => Err(SvgError {
+                line: self.line,
+                reason: format!("missing '{}'", terminator),
+            }),
+        }
+    }
+
+    fn parse_tag(&self, content: &str) -> Result<Tag, SvgError> {
+        let fail = |reason: String| SvgError {
+            line: self.line,
+            reason,
+        };
+        let (kind, content) = if let Some(rest) = content.strip_prefix('/') {
+            (TagKind::End, rest)
+        } else if let Some(rest) = content.strip_suffix('/') {
+            (TagKind::Empty, rest)
+        } else {
+            (TagKind::Start, content)
+        };
+        let content = content.trim();
+        let name_end = content
+            .find(|c: char| c.is_whitespace())
+            .unwrap_or(content.len());
+        let name = content[..name_end].to_string();
+
+        let mut attributes = HashMap::new();
+        let mut rest = content[name_end..].trim_start();
+        while !rest.is_empty() {
+            let equals = rest
+                .find('=')
+                .ok_or_else(|| fail(format!("attribute without value in <{}>", name)))?;
+            let key = rest[..equals].trim().to_string();
+            let value_part = rest[equals + 1..].trim_start();
+            let quote = value_part
+                .chars()
+                .next()
+                .filter(|c| *c == '"' || *c == '\'')
+                .ok_or_else(|| fail(format!("unquoted attribute {} in <{}>", key, name)))?;
+            let close = value_part[1..]
+                .find(quote)
+                .ok_or_else(|| fail(format!("unterminated attribute {} in <{}>", key, name)))?;
+            attributes.insert(key, decode_entities(&value_part[1..close + 1]));
+            rest = value_part[close + 2..].trim_start();
+        }
+        Ok(Tag {
+            name,
+            kind,
+            attributes,
+            line: self.line,
+        })
+    }
+}
+
+impl Iterator for Tags<'_> {
+    type Item = Result<Tag, SvgError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let offset = self.text[self.position..].find('<')?;
+            self.advance_to(self.position + offset);
+            let rest = &self.text[self.position..];
+            let skipped = if rest.starts_with("<!--") {
+                self.skip_past("-->")
+            } else if rest.starts_with("<![CDATA[") {
+                self.skip_past("]]>")
+            } else if rest.starts_with("<?") {
+                self.skip_past("?>")
+            } else if rest.starts_with("<!") {
+                self.skip_past(">")
+            } else {
+                break;
+            };
+            if let Err(e) = skipped {
+                self.position = self.text.len();
+                return Some(Err(e));
+            }
+        }
+
+        // find the closing '>' outside of quoted attribute values
+        let bytes = self.text.as_bytes();
+        let mut quote = None;
+        let mut end = self.position + 1;
+        while end < bytes.len() {
+            match (quote, bytes[end]) {
+                (None, b'"' | b'\'') => quote = Some(bytes[end]),
+                (Some(q), c) if c == q => quote = None,
+                (None, b'>') => break,
+                _ => {}
+            }
+            end += 1;
+        }
+        if end >= bytes.len() {
+            self.position = self.text.len();
+            return Some(Err(SvgError {
+                line: self.line,
+                reason: "unterminated tag".to_string(),
+            }));
+        }
+        let tag = self.parse_tag(&self.text[self.position + 1..end]);
+        self.advance_to(end + 1);
+        Some(tag)
+    }
+}
+
+fn decode_entities(value: &str) -> String {
+    if !value.contains('&') {
+        return value.to_string();
+    }
+    value
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn path_commands() {
+        let subpaths = parse_path_data("M10,10 h10 v10 H10 z m5,5 l1-1 1.5.5", 0.01).unwrap();
+        assert_eq!(subpaths.len(), 2);
+        assert!(subpaths[0].closed);
+        assert_eq!(
+            subpaths[0].points,
+            vec![
+                Point { x: 10.0, y: 10.0 },
+                Point { x: 20.0, y: 10.0 },
+                Point { x: 20.0, y: 20.0 },
+                Point { x: 10.0, y: 20.0 },
+            ]
+        );
+        // the relative moveto after closepath starts at the start of the first subpath
+        assert_eq!(
+            subpaths[1].points,
+            vec![
+                Point { x: 15.0, y: 15.0 },
+                Point { x: 16.0, y: 14.0 },
+                Point { x: 17.5, y: 14.5 },
+            ]
+        );
+    }
+
+    #[test]
+    fn arc_and_curves_stay_within_tolerance() {
+        // full circle of radius 10 around (10, 0) as two arcs with flags written without separators
+        let subpaths = parse_path_data("M0,0 a10,10 0 1,0 20,0 a10 10 0 10-20 0z", 0.01).unwrap();
+        for p in &subpaths[0].points {
+            let radius = ((p.x - 10.0).powi(2) + p.y.powi(2)).sqrt();
+            assert!((radius - 10.0).abs() < 0.011);
+        }
+        let curve = parse_path_data("M0 0 C0 10 10 10 10 0 S 20 -10 20 0 Q25 5 30 0 T40 0", 0.1);
+        assert_eq!(
+            curve.unwrap()[0].points.last(),
+            Some(&Point { x: 40.0, y: 0.0 })
+        );
+    }
+
+    #[test]
+    fn huge_arcs_and_numbers() {
+        let subpaths = parse_path_data("M 0 0 A 1e300 1e300 0 0 0 10 10", 0.1).unwrap();
+        assert!(subpaths[0].points.len() <= (1 << 16) + 1);
+        assert_eq!(subpaths[0].points.last(), Some(&Point { x: 10.0, y: 10.0 }));
+
+        for tolerance in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(parse_path_data("M0 0 A1 1 0 0 0 2 0", tolerance).is_err());
+            assert!(SvgDocument::parse_with_tolerance("<svg/>", tolerance).is_err());
+        }
+
+        let error = parse_path_data("M0,0l1e400,0", 0.01).unwrap_err();
+        assert_eq!(error.reason, "number out of range at '1e400'");
+    }
+
+    #[test]
+    fn malformed_number_before_umlauts() {
+        // the 20th byte after the sign lies inside an 'ä'
+        let error = parse_path_data("M0 0 L-ääääääääääääää", 0.01).unwrap_err();
+        assert!(error.reason.contains("-äääää"), "{}", error.reason);
+    }
+
+    #[test]
+    fn document_with_transforms() {
+        let svg = r#"<?xml version="1.0"?>
+            <!-- comment with <tags> -->
+            <svg xmlns="http://www.w3.org/2000/svg">
+              <g transform="translate(100 0)">
+                <g transform="scale(2)">
+                  <path id="state" d="M0 0 L1 0 L1 1 Z"/>
+                </g>
+                <path sodipodi:type="arc" sodipodi:cx="1" sodipodi:cy="2" id="city" d="m 0 0"/>
+              </g>
+              <path id="outside" d="M0 0 L1 0 L1 1 Z"/>
+            </svg>"#;
+        let document = SvgDocument::parse(svg).unwrap();
+        let polygons = document.labeled_polygons();
+        assert_eq!(polygons[0].0, "state");
+        assert_eq!(polygons[0].1[0][2], Point { x: 102.0, y: 2.0 });
+        assert_eq!(polygons[1].1[0][2], Point { x: 1.0, y: 1.0 });
+        assert_eq!(
+            document.labeled_points(),
+            vec![("city".to_string(), Point { x: 101.0, y: 2.0 })]
+        );
+    }
+}
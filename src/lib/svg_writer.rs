@@ -0,0 +1,366 @@
+use crate::lib::{line::Line, point::Point};
+use std::{fmt::Write, fs, io};
+
+// Orientation of the y-axis in the written document. SVG itself points down, our data files
+// (segments, polygons) assume the usual mathematical orientation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum YAxis {
+    Up,
+    Down,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Style {
+    pub stroke: Option<String>,
+    // all sizes are in user units, i.e. the units of the geometry
+    pub stroke_width: f64,
+    pub fill: Option<String>,
+    pub opacity: f64,
+    pub point_radius: f64,
+    pub font_size: f64,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self {
+            stroke: Some("black".to_string()),
+            stroke_width: 1.0,
+            fill: None,
+            opacity: 1.0,
+            point_radius: 2.0,
+            font_size: 12.0,
+        }
+    }
+}
+
+impl Style {
+    pub fn stroke(color: &str, width: f64) -> Self {
+        Self {
+            stroke: Some(color.to_string()),
+            stroke_width: width,
+            ..Self::default()
+        }
+    }
+
+    pub fn fill(color: &str) -> Self {
+        Self {
+            stroke: None,
+            fill: Some(color.to_string()),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_fill(self, color: &str) -> Self {
+        Self {
+            fill: Some(color.to_string()),
+            ..self
+        }
+    }
+
+    pub fn with_opacity(self, opacity: f64) -> Self {
+        Self { opacity, ..self }
+    }
+
+    pub fn with_point_radius(self, point_radius: f64) -> Self {
+        Self {
+            point_radius,
+            ..self
+        }
+    }
+
+    pub fn with_font_size(self, font_size: f64) -> Self {
+        Self { font_size, ..self }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Element {
+    Point(Point),
+    Line(Point, Point),
+    Polyline(Vec<Point>),
+    // rings, drawn with the even-odd rule so holes stay empty
+    Polygon(Vec<Vec<Point>>),
+    Circle(Point, f64),
+    Label(Point, String),
+}
+
+// One `<g>` of the document with a common style.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Layer {
+    name: String,
+    style: Style,
+    elements: Vec<Element>,
+}
+
+impl Layer {
+    pub fn point(&mut self, point: &Point) -> &mut Self {
+        self.elements.push(Element::Point(*point));
+        self
+    }
+
+    pub fn points<'a>(&mut self, points: impl IntoIterator<Item = &'a Point>) -> &mut Self {
+        self.elements
+            .extend(points.into_iter().map(|p| Element::Point(*p)));
+        self
+    }
+
+    pub fn line(&mut self, line: &Line) -> &mut Self {
+        self.elements.push(Element::Line(line.p1, line.p2));
+        self
+    }
+
+    pub fn lines<'a>(&mut self, lines: impl IntoIterator<Item = &'a Line>) -> &mut Self {
+        self.elements
+            .extend(lines.into_iter().map(|l| Element::Line(l.p1, l.p2)));
+        self
+    }
+
+    pub fn polyline(&mut self, points: &[Point]) -> &mut Self {
+        self.elements.push(Element::Polyline(points.to_vec()));
+        self
+    }
+
+    pub fn polygon(&mut self, rings: &[Vec<Point>]) -> &mut Self {
+        self.elements.push(Element::Polygon(rings.to_vec()));
+        self
+    }
+
+    pub fn circle(&mut self, center: &Point, radius: f64) -> &mut Self {
+        self.elements.push(Element::Circle(*center, radius));
+        self
+    }
+
+    pub fn label(&mut self, position: &Point, text: &str) -> &mut Self {
+        self.elements
+            .push(Element::Label(*position, text.to_string()));
+        self
+    }
+}
+
+pub struct SvgWriter {
+    y_axis: YAxis,
+    // space around the geometry, in user units
+    pub margin: f64,
+    // width of the image in pixels, the height follows from the aspect ratio
+    pub width: f64,
+    layers: Vec<Layer>,
+}
+
+impl SvgWriter {
+    pub fn new(y_axis: YAxis) -> Self {
+        Self {
+            y_axis,
+            margin: 10.0,
+            width: 1000.0,
+            layers: Vec::new(),
+        }
+    }
+
+    // Layers are drawn in the order they are added.
+    pub fn layer(&mut self, name: &str, style: Style) -> &mut Layer {
+        self.layers.push(Layer {
+            name: name.to_string(),
+            style,
+            elements: Vec::new(),
+        });
+        self.layers.last_mut().unwrap()
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        fs::write(path, self.to_svg_string())
+    }
+
+    pub fn to_svg_string(&self) -> String {
+        let (min, max) = self.bounding_box();
+        let (min_x, min_y) = (min.x - self.margin, min.y - self.margin);
+        let (view_width, view_height) = (
+            max.x - min.x + 2.0 * self.margin,
+            max.y - min.y + 2.0 * self.margin,
+        );
+        // mirroring at the center of the bounding box keeps the view box unchanged
+        let flip = |p: &Point| match self.y_axis {
+            YAxis::Down => *p,
+            YAxis::Up => Point {
+                x: p.x,
+                y: min.y + max.y - p.y,
+            },
+        };
+
+        let mut svg = String::new();
+        writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="{} {} {} {}">"#,
+            self.width,
+            self.width * view_height / view_width.max(f64::MIN_POSITIVE),
+            min_x,
+            min_y,
+            view_width,
+            view_height
+        )
+        .unwrap();
+
+        for layer in &self.layers {
+            let style = &layer.style;
+            writeln!(
+                svg,
+                r#"  <g id="{}" stroke="{}" stroke-width="{}" fill="{}" opacity="{}" font-size="{}" fill-rule="evenodd">"#,
+                escape(&layer.name),
+                style.stroke.as_deref().map_or("none".to_string(), escape),
+                style.stroke_width,
+                style.fill.as_deref().map_or("none".to_string(), escape),
+                style.opacity,
+                style.font_size
+            )
+            .unwrap();
+            for element in &layer.elements {
+                match element {
+                    Element::Point(p) => {
+                        let p = flip(p);
+                        // points are always visible, even in layers without fill
+                        let color = style.fill.as_ref().or(style.stroke.as_ref());
+                        writeln!(
+                            svg,
+                            r#"    <circle cx="{}" cy="{}" r="{}" fill="{}" stroke="none"/>"#,
+                            p.x,
+                            p.y,
+                            style.point_radius,
+                            color.map_or("black".to_string(), |c| escape(c))
+                        )
+                        .unwrap();
+                    }
+                    Element::Line(a, b) => {
+                        let (a, b) = (flip(a), flip(b));
+                        writeln!(
+                            svg,
+                            r#"    <line x1="{}" y1="{}" x2="{}" y2="{}"/>"#,
+                            a.x, a.y, b.x, b.y
+                        )
+                        .unwrap();
+                    }
+                    Element::Polyline(points) => {
+                        let points: Vec<String> = points
+                            .iter()
+                            .map(|p| {
+                                let p = flip(p);
+                                format!("{},{}", p.x, p.y)
+                            })
+                            .collect();
+                        writeln!(svg, r#"    <polyline points="{}"/>"#, points.join(" ")).unwrap();
+                    }
+                    Element::Polygon(rings) => {
+                        let mut d = String::new();
+                        for ring in rings {
+                            for (i, p) in ring.iter().enumerate() {
+                                let p = flip(p);
+                                write!(d, "{}{},{} ", if i == 0 { "M" } else { "L" }, p.x, p.y)
+                                    .unwrap();
+                            }
+                            d.push_str("Z ");
+                        }
+                        writeln!(svg, r#"    <path d="{}"/>"#, d.trim_end()).unwrap();
+                    }
+                    Element::Circle(center, radius) => {
+                        let center = flip(center);
+                        writeln!(
+                            svg,
+                            r#"    <circle cx="{}" cy="{}" r="{}"/>"#,
+                            center.x, center.y, radius
+                        )
+                        .unwrap();
+                    }
+                    Element::Label(position, text) => {
+                        let position = flip(position);
+                        // text is filled, not stroked
+                        writeln!(
+                            svg,
+                            r#"    <text x="{}" y="{}" fill="{}" stroke="none">{}</text>"#,
+                            position.x,
+                            position.y,
+                            style
+                                .fill
+                                .as_ref()
+                                .or(style.stroke.as_ref())
+                                .map_or("black".to_string(), |c| escape(c)),
+                            escape(text)
+                        )
+                        .unwrap();
+                    }
+                }
+            }
+            svg.push_str("  </g>\n");
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    fn bounding_box(&self) -> (Point, Point) {
+        let mut min = Point {
+            x: f64::INFINITY,
+            y: f64::INFINITY,
+        };
+        let mut max = Point {
+            x: f64::NEG_INFINITY,
+            y: f64::NEG_INFINITY,
+        };
+        let mut add = |p: &Point, extent: f64| {
+            min.x = min.x.min(p.x - extent);
+            min.y = min.y.min(p.y - extent);
+            max.x = max.x.max(p.x + extent);
+            max.y = max.y.max(p.y + extent);
+        };
+        for layer in &self.layers {
+            for element in &layer.elements {
+                match element {
+                    Element::Point(p) => add(p, layer.style.point_radius),
+                    Element::Line(a, b) => {
+                        add(a, 0.0);
+                        add(b, 0.0);
+                    }
+                    Element::Polyline(points) => points.iter().for_each(|p| add(p, 0.0)),
+                    Element::Polygon(rings) => rings.iter().flatten().for_each(|p| add(p, 0.0)),
+                    Element::Circle(center, radius) => add(center, *radius),
+                    Element::Label(position, _) => add(position, 0.0),
+                }
+            }
+        }
+        if min.x > max.x {
+            // empty document
+            return (Point { x: 0.0, y: 0.0 }, Point { x: 0.0, y: 0.0 });
+        }
+        (min, max)
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lib::svg_reader::SvgDocument;
+
+    #[test]
+    fn flipped_polygon_reads_back() {
+        let square = vec![
+            Point { x: 0.0, y: 0.0 },
+            Point { x: 4.0, y: 0.0 },
+            Point { x: 4.0, y: 2.0 },
+            Point { x: 0.0, y: 2.0 },
+        ];
+        let mut svg = SvgWriter::new(YAxis::Up);
+        svg.layer("shapes", Style::fill("red"))
+            .polygon(&[square])
+            .label(&Point { x: 1.0, y: 1.0 }, "a < b");
+        let text = svg.to_svg_string();
+        assert!(text.contains("a &lt; b"));
+
+        let path = &SvgDocument::parse(&text).unwrap().paths[0];
+        // (0, 0) is the lower left corner, i.e. at the bottom of the image
+        assert_eq!(path.subpaths[0].points[0], Point { x: 0.0, y: 2.0 });
+        assert_eq!(path.subpaths[0].points[2], Point { x: 4.0, y: 0.0 });
+    }
+}
@@ -0,0 +1,497 @@
+use crate::lib::{
+    ccw::crossing_point,
+    common::{EPSILON, SNAP_DISTANCE, SWEEP_TOLERANCE},
+    event::{
+        Event, EventHeap,
+        EventType::{End, Intersection as Crossing, Start},
+    },
+    line::Line,
+    line_with_ord::{LineWithOrd, SharedPosition, SweepPosition, shared_position},
+    point::Point,
+    sweep_status::{BTreeStatus, SweepStatus},
+};
+use std::{collections::HashSet, ops::ControlFlow};
+
+// Bentley-Ottmann sweep line. Segments are identified by their index in the input. Every pair
+// of segments for which `Line::crosses` holds is reported once, at the first point they share
+// (touching, overlapping and vertical segments included).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Intersection {
+    pub point: Point,
+    // the smaller index comes first
+    pub segments: (usize, usize),
+}
+
+pub fn intersections(lines: impl IntoIterator<Item = Line>) -> Vec<Intersection> {
+    intersections_with::<BTreeStatus>(lines)
+}
+
+// Like `intersections`, with a different status structure for the sweep line.
+pub fn intersections_with<S: SweepStatus>(
+    lines: impl IntoIterator<Item = Line>,
+) -> Vec<Intersection> {
+    let mut result = Vec::new();
+    let _ = visit_intersections_with::<S, _>(lines, |intersection| {
+        result.push(*intersection);
+        ControlFlow::<()>::Continue(())
+    });
+    result
+}
+
+// For fallible input like a `SegmentReader`: the first error is returned instead.
+pub fn try_intersections<E>(
+    lines: impl IntoIterator<Item = Result<Line, E>>,
+) -> Result<Vec<Intersection>, E> {
+    try_intersections_with::<BTreeStatus, E>(lines)
+}
+
+pub fn try_intersections_with<S: SweepStatus, E>(
+    lines: impl IntoIterator<Item = Result<Line, E>>,
+) -> Result<Vec<Intersection>, E> {
+    // the sweep keeps all segments anyway, so this is the only copy
+    let lines = lines.into_iter().collect::<Result<Vec<_>, E>>()?;
+    Ok(intersections_with::<S>(lines))
+}
+
+pub fn count_intersections(lines: impl IntoIterator<Item = Line>) -> usize {
+    let mut count = 0;
+    let _ = visit_intersections(lines, |_| {
+        count += 1;
+        ControlFlow::<()>::Continue(())
+    });
+    count
+}
+
+// Calls `visitor` for every intersection in sweep order, returning `ControlFlow::Break` stops
+// the sweep.
+pub fn visit_intersections<B>(
+    lines: impl IntoIterator<Item = Line>,
+    visitor: impl FnMut(&Intersection) -> ControlFlow<B>,
+) -> ControlFlow<B> {
+    visit_intersections_with::<BTreeStatus, B>(lines, visitor)
+}
+
+pub fn visit_intersections_with<S: SweepStatus, B>(
+    lines: impl IntoIterator<Item = Line>,
+    mut visitor: impl FnMut(&Intersection) -> ControlFlow<B>,
+) -> ControlFlow<B> {
+    Sweep::<S>::new(lines.into_iter().collect(), None).run(&mut visitor)
+}
+
+// Splits the input of a sweep into red segments, the first `red` ones, and blue segments.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Colours {
+    pub red: usize,
+    // Neither colour crosses itself, so neighbours of the same colour are not checked.
+    pub non_crossing: bool,
+}
+
+// Like `visit_intersections_with`, but only pairs of a red and a blue segment are reported.
+pub(crate) fn visit_coloured_intersections<S: SweepStatus, B>(
+    lines: impl IntoIterator<Item = Line>,
+    colours: Colours,
+    mut visitor: impl FnMut(&Intersection) -> ControlFlow<B>,
+) -> ControlFlow<B> {
+    Sweep::<S>::new(lines.into_iter().collect(), Some(colours)).run(&mut visitor)
+}
+
+// The id of probes, no segment has it.
+const PROBE: usize = usize::MAX;
+
+struct Sweep<S> {
+    lines: Vec<Line>,
+    // The BinaryHeap is a min heap
+    // => It is sorted while inserting and always pops the smallest element first
+    queue: EventHeap,
+    // read by the comparator of every entry in the status
+    position: SharedPosition,
+    status: S,
+    // segments can become neighbours several times, but their crossing is only queued once
+    scheduled: HashSet<(usize, usize)>,
+    // overlapping segments meet in more than one event point
+    reported: HashSet<(usize, usize)>,
+    colours: Option<Colours>,
+}
+
+impl<S: SweepStatus> Sweep<S> {
+    fn new(lines: Vec<Line>, colours: Option<Colours>) -> Self {
+        let mut queue = EventHeap::new();
+        for (id, line) in lines.iter().enumerate() {
+            let (left, right) = endpoints(line);
+            queue.push(Event::new(left, Start(id)));
+            queue.push(Event::new(right, End(id)));
+        }
+        Self {
+            lines,
+            queue,
+            position: shared_position(),
+            status: S::default(),
+            scheduled: HashSet::new(),
+            reported: HashSet::new(),
+            colours,
+        }
+    }
+
+    fn run<B>(
+        mut self,
+        visitor: &mut impl FnMut(&Intersection) -> ControlFlow<B>,
+    ) -> ControlFlow<B> {
+        while let Some(event) = self.queue.pop() {
+            let p = event.point;
+            let mut events = vec![event];
+            // events at almost the same point can be separated by events with a slightly
+            // smaller x and a larger y
+            let mut elsewhere = Vec::new();
+            while self
+                .queue
+                .peek()
+                .is_some_and(|next| next.point.x - p.x <= SWEEP_TOLERANCE)
+            {
+                let next = self.queue.pop().unwrap();
+                if (next.point.y - p.y).abs() <= SWEEP_TOLERANCE {
+                    events.push(next);
+                } else {
+                    elsewhere.push(next);
+                }
+            }
+            self.queue.extend(elsewhere);
+            self.move_to(p, false);
+            self.handle_event_point(&events, visitor)?;
+        }
+        ControlFlow::Continue(())
+    }
+
+    // All segments that start, end or cross at the current position are handled together.
+    fn handle_event_point<B>(
+        &mut self,
+        events: &[Event],
+        visitor: &mut impl FnMut(&Intersection) -> ControlFlow<B>,
+    ) -> ControlFlow<B> {
+        let p = self.position.get().point;
+        // The segments at most SWEEP_TOLERANCE above or below p pass through it. They are
+        // listed in the stored order, comparing them with each other could give a different
+        // one: almost collinear segments are ordered by rounding errors.
+        self.position.set_through(&[], &[]);
+        let run: Vec<usize> = self
+            .status
+            .range(
+                &self.probe(p.y - SWEEP_TOLERANCE),
+                &self.probe(p.y + SWEEP_TOLERANCE),
+            )
+            .iter()
+            .map(|entry| entry.id)
+            .collect();
+        self.position.set_through(&run, &[]);
+
+        let mut starting = Vec::new();
+        let mut ending = Vec::new();
+        let mut through = run.clone();
+        for event in events {
+            match event.event_type {
+                Start(id) => starting.push(id),
+                End(id) => ending.push(id),
+                // numerically the crossing segments might miss the point
+                Crossing(below, above) => through.extend([below, above]),
+            }
+        }
+        through.extend(&ending);
+        through.sort_unstable();
+        through.dedup();
+        through.retain(|&id| run.contains(&id) || self.status.contains(&self.entry(id)));
+
+        let mut involved = starting.clone();
+        involved.extend(&through);
+        for (i, &a) in involved.iter().enumerate() {
+            for &b in &involved[i + 1..] {
+                let pair = (a.min(b), a.max(b));
+                if !self.same_colour(a, b)
+                    && self.lines[a].crosses(&self.lines[b])
+                    && self.reported.insert(pair)
+                {
+                    visitor(&Intersection {
+                        point: p,
+                        segments: pair,
+                    })?;
+                }
+            }
+        }
+
+        // segments of length zero end where they start
+        let mut continuing: Vec<usize> = involved
+            .iter()
+            .copied()
+            .filter(|id| !ending.contains(id))
+            .collect();
+        // right after p the steeper segments are above, overlapping ones keep their ids' order
+        continuing.sort_by(|&a, &b| {
+            let (a, b) = (self.entry(a), self.entry(b));
+            a.slope()
+                .partial_cmp(&b.slope())
+                .unwrap()
+                .then(a.id.cmp(&b.id))
+        });
+        self.position.set_through(&run, &continuing);
+
+        // two segments crossing in their interiors only change places
+        let swap = match (&run[..], events, &continuing[..]) {
+            (&[lower, upper], [event], &[first, second])
+                if matches!(event.event_type, Crossing(..))
+                    && through.len() == 2
+                    && (first, second) == (upper, lower) =>
+            {
+                Some((self.entry(lower), self.entry(upper)))
+            }
+            _ => None,
+        };
+        if let Some((lower, upper)) = &swap {
+            self.status.swap(lower, upper);
+        } else {
+            for &id in &through {
+                self.status.remove(&self.entry(id));
+            }
+        }
+        self.move_to(p, true);
+        if swap.is_none() {
+            for &id in &continuing {
+                self.status.insert(self.entry(id));
+            }
+        }
+
+        match (continuing.first(), continuing.last()) {
+            (Some(&lowest), Some(&highest)) => {
+                if let Some(below) = self.status.predecessor(&self.entry(lowest)) {
+                    self.schedule(below.id, lowest);
+                }
+                if let Some(above) = self.status.successor(&self.entry(highest)) {
+                    self.schedule(highest, above.id);
+                }
+            }
+            _ => {
+                let probe = self.probe(p.y);
+                let below = self.status.predecessor(&probe);
+                let above = self.status.ceiling(&probe);
+                if let (Some(below), Some(above)) = (below, above) {
+                    self.schedule(below.id, above.id);
+                }
+            }
+        }
+        ControlFlow::Continue(())
+    }
+
+    // Adds the intersection event of two neighbours if they cross behind the current position.
+    fn schedule(&mut self, below: usize, above: usize) {
+        if self.colours.is_some_and(|colours| colours.non_crossing)
+            && self.same_colour(below, above)
+        {
+            return;
+        }
+        let (a, b) = (&self.lines[below], &self.lines[above]);
+        if !a.crosses(b) {
+            return;
+        }
+        // a crossing point on a vertical segment gets its exact x
+        let (a, b) = if (b.p1.x - b.p2.x).abs() < EPSILON {
+            (b, a)
+        } else {
+            (a, b)
+        };
+        // collinear segments are reported where one of them starts
+        let q = crossing_point(&a.p1, &a.p2, &b.p1, &b.p2);
+        if !q.x.is_finite() || !q.y.is_finite() {
+            return;
+        }
+        // segments touching at an endpoint cross exactly there, a rounded point could come
+        // before events of other segments at that endpoint
+        let q = [a.p1, a.p2, b.p1, b.p2]
+            .into_iter()
+            .find(|endpoint| endpoint.distance(&q) <= SNAP_DISTANCE)
+            .unwrap_or(q);
+        let p = self.position.get().point;
+        let behind = q.x - p.x > SWEEP_TOLERANCE
+            || (q.x - p.x).abs() <= SWEEP_TOLERANCE && q.y - p.y > SWEEP_TOLERANCE;
+        if behind && self.scheduled.insert((below.min(above), below.max(above))) {
+            self.queue.push(Event::new(q, Crossing(below, above)));
+        }
+    }
+
+    fn same_colour(&self, a: usize, b: usize) -> bool {
+        self.colours
+            .is_some_and(|colours| (a < colours.red) == (b < colours.red))
+    }
+
+    fn move_to(&self, point: Point, after: bool) {
+        self.position.set(SweepPosition { point, after });
+    }
+
+    fn entry(&self, id: usize) -> LineWithOrd {
+        LineWithOrd::new(self.lines[id].clone(), id, self.position.clone())
+    }
+
+    // A point segment at height `y` above or below the current position.
+    fn probe(&self, y: f64) -> LineWithOrd {
+        let point = Point {
+            x: self.position.get().point.x,
+            y,
+        };
+        LineWithOrd::new(Line::new(point, point), PROBE, self.position.clone())
+    }
+}
+
+// left and right endpoint, the lower one first for vertical segments
+fn endpoints(line: &Line) -> (Point, Point) {
+    if (line.p1.x, line.p1.y) <= (line.p2.x, line.p2.y) {
+        (line.p1, line.p2)
+    } else {
+        (line.p2, line.p1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lib::{
+        generator::SegmentGenerator,
+        segment_reader::SegmentReader,
+        sweep_status::{SkipListStatus, TreapStatus},
+        verify::brute_force,
+    };
+
+    fn line(x1: f64, y1: f64, x2: f64, y2: f64) -> Line {
+        Line::new(Point { x: x1, y: y1 }, Point { x: x2, y: y2 })
+    }
+
+    fn pairs(lines: &[Line]) -> Vec<(usize, usize)> {
+        pairs_with::<BTreeStatus>(lines)
+    }
+
+    fn pairs_with<S: SweepStatus>(lines: &[Line]) -> Vec<(usize, usize)> {
+        let mut pairs: Vec<_> = intersections_with::<S>(lines.iter().cloned())
+            .iter()
+            .map(|intersection| intersection.segments)
+            .collect();
+        pairs.sort_unstable();
+        pairs
+    }
+
+    #[test]
+    fn reports_segment_indices() {
+        let lines = vec![
+            line(0.0, 0.0, 4.0, 4.0),
+            line(10.0, 0.0, 11.0, 1.0),
+            line(0.0, 4.0, 4.0, 0.0),
+        ];
+        let found = intersections(lines.clone());
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].segments, (0, 2));
+        assert!((found[0].point.x - 2.0).abs() < 1e-9 && (found[0].point.y - 2.0).abs() < 1e-9);
+        assert_eq!(count_intersections(lines.clone()), 1);
+
+        let mut visited = 0;
+        let stopped = visit_intersections(lines, |_| {
+            visited += 1;
+            ControlFlow::Break(())
+        });
+        assert_eq!((stopped, visited), (ControlFlow::Break(()), 1));
+    }
+
+    #[test]
+    fn reads_fallible_input() {
+        let text = "0 0 4 4\n10 0 11 1\n0 4 4 0\n";
+        let found = try_intersections(SegmentReader::new(text.as_bytes(), "test")).unwrap();
+        assert_eq!(
+            found,
+            intersections(crate::lib::data::parse_lines(text, "").unwrap())
+        );
+
+        let reader = SegmentReader::new("0 0 4 4\n0 4 x 0\n".as_bytes(), "test");
+        let error = try_intersections_with::<TreapStatus, _>(reader).unwrap_err();
+        assert_eq!((error.line, error.column), (2, 5));
+    }
+
+    #[test]
+    fn degenerate_cases() {
+        let lines = vec![
+            // vertical segments, one crossing two others and overlapping a third
+            line(2.0, -1.0, 2.0, 5.0),
+            line(0.0, 0.0, 4.0, 4.0),
+            line(0.0, 4.0, 4.0, 0.0),
+            line(2.0, 4.0, 2.0, 7.0),
+            // shared endpoints and a segment ending on another one
+            line(4.0, 4.0, 6.0, 4.0),
+            line(4.0, 4.0, 6.0, 0.0),
+            line(5.0, 4.0, 5.0, 3.0),
+            // collinear overlap and a point
+            line(0.0, 0.0, -2.0, -2.0),
+            line(-1.0, -1.0, -3.0, -3.0),
+            line(-2.0, -2.0, -2.0, -2.0),
+        ];
+        assert_eq!(pairs(&lines), brute_force(&lines));
+        // three segments through (2, 2)
+        let at_center: Vec<_> = intersections(lines.iter().cloned())
+            .into_iter()
+            .filter(|i| i.point == Point { x: 2.0, y: 2.0 })
+            .map(|i| i.segments)
+            .collect();
+        assert_eq!(at_center, vec![(0, 1), (0, 2), (1, 2)]);
+    }
+
+    #[test]
+    fn collinear_overlaps() {
+        // the second segment lies on the first one and both end in the same point
+        let lines = vec![
+            line(
+                40.89351514454011,
+                889.1540393453229,
+                77.26941256354259,
+                916.6581022384391,
+            ),
+            line(
+                64.84219093608021,
+                907.2617956321349,
+                77.26941256354259,
+                916.6581022384391,
+            ),
+        ];
+        assert_eq!(pairs_with::<BTreeStatus>(&lines), vec![(0, 1)]);
+        assert_eq!(pairs_with::<SkipListStatus>(&lines), vec![(0, 1)]);
+        assert_eq!(pairs_with::<TreapStatus>(&lines), vec![(0, 1)]);
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        for seed in 0..5 {
+            let mut generator =
+                SegmentGenerator::new(seed, Point { x: 0.0, y: 0.0 }, Point { x: 100.0, y: 100.0 });
+            for lines in [
+                generator.uniform(400, 20.0),
+                generator.vertical(200, 20.0),
+                generator.shared_endpoints(200, 20.0, 10),
+                generator.grid_snapped(400, 15.0, 5.0),
+                generator.concurrent(30, 60.0),
+                generator.collinear(40, 20.0),
+            ] {
+                let expected = brute_force(&lines);
+                assert_eq!(pairs_with::<BTreeStatus>(&lines), expected);
+                assert_eq!(pairs_with::<SkipListStatus>(&lines), expected);
+                assert_eq!(pairs_with::<TreapStatus>(&lines), expected);
+            }
+        }
+    }
+
+    // needs the course data next to the repository
+    #[test]
+    #[ignore]
+    fn matches_brute_force_on_course_data() {
+        for file in [
+            "../data/01/s_1000_1.dat",
+            "../data/01/s_1000_10.dat",
+            "../data/01/s_10000_1.dat",
+        ] {
+            let lines: Vec<Line> = SegmentReader::open(file)
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap();
+            assert_eq!(pairs(&lines), brute_force(&lines), "{}", file);
+        }
+    }
+}
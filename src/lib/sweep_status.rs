@@ -0,0 +1,574 @@
+use crate::lib::{
+    generator::Rng,
+    line_with_ord::{LineWithOrd, SweepPosition},
+};
+use std::{
+    cmp::Ordering,
+    collections::BTreeSet,
+    ops::Bound::{Excluded, Unbounded},
+};
+
+// The segments on the sweep line, ordered from bottom to top. All entries share one sweep
+// position, the sweep moves it only where the order of the stored entries stays valid.
+pub trait SweepStatus: Default {
+    fn insert(&mut self, entry: LineWithOrd);
+    fn remove(&mut self, entry: &LineWithOrd) -> bool;
+    fn contains(&self, entry: &LineWithOrd) -> bool;
+    // smallest entry >= key
+    fn ceiling(&self, key: &LineWithOrd) -> Option<&LineWithOrd>;
+    // largest entry < key
+    fn predecessor(&self, key: &LineWithOrd) -> Option<&LineWithOrd>;
+    // smallest entry > key
+    fn successor(&self, key: &LineWithOrd) -> Option<&LineWithOrd>;
+    // All entries >= low and <= high, bottom to top. Only the bounds are compared with the
+    // entries, the ones in between are listed in the stored order.
+    fn range(&self, low: &LineWithOrd, high: &LineWithOrd) -> Vec<&LineWithOrd>;
+    // `lower` lies directly below `upper` and both cross at the current position. Afterwards
+    // `upper` lies directly below `lower`, their order right after the crossing.
+    fn swap(&mut self, lower: &LineWithOrd, upper: &LineWithOrd);
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[derive(Default)]
+pub struct BTreeStatus {
+    set: BTreeSet<LineWithOrd>,
+}
+
+impl SweepStatus for BTreeStatus {
+    fn insert(&mut self, entry: LineWithOrd) {
+        self.set.insert(entry);
+    }
+
+    fn remove(&mut self, entry: &LineWithOrd) -> bool {
+        self.set.remove(entry)
+    }
+
+    fn contains(&self, entry: &LineWithOrd) -> bool {
+        self.set.contains(entry)
+    }
+
+    fn ceiling(&self, key: &LineWithOrd) -> Option<&LineWithOrd> {
+        self.set.range(key..).next()
+    }
+
+    fn predecessor(&self, key: &LineWithOrd) -> Option<&LineWithOrd> {
+        self.set.range(..key).next_back()
+    }
+
+    fn successor(&self, key: &LineWithOrd) -> Option<&LineWithOrd> {
+        self.set.range((Excluded(key), Unbounded)).next()
+    }
+
+    fn range(&self, low: &LineWithOrd, high: &LineWithOrd) -> Vec<&LineWithOrd> {
+        self.set.range(low..=high).collect()
+    }
+
+    // The set cannot reorder its elements in place, so both are inserted again in the order
+    // right after the crossing.
+    fn swap(&mut self, lower: &LineWithOrd, upper: &LineWithOrd) {
+        let position = lower.sweep.get();
+        let removed: Vec<_> = [lower, upper]
+            .into_iter()
+            .filter_map(|entry| self.set.take(entry))
+            .collect();
+        lower.sweep.set(SweepPosition {
+            after: true,
+            ..position
+        });
+        self.set.extend(removed);
+        lower.sweep.set(position);
+    }
+
+    fn len(&self) -> usize {
+        self.set.len()
+    }
+}
+
+// Marks a missing node in the arenas of the skip list and the treap.
+const NIL: usize = usize::MAX;
+const MAX_LEVEL: usize = 32;
+
+struct SkipNode {
+    // None only for the head
+    entry: Option<LineWithOrd>,
+    next: Vec<usize>,
+}
+
+// Skip list with nodes in an arena, node 0 is the head.
+pub struct SkipListStatus {
+    nodes: Vec<SkipNode>,
+    free: Vec<usize>,
+    rng: Rng,
+    len: usize,
+}
+
+impl Default for SkipListStatus {
+    fn default() -> Self {
+        Self {
+            nodes: vec![SkipNode {
+                entry: None,
+                next: vec![NIL; MAX_LEVEL],
+            }],
+            free: Vec::new(),
+            rng: Rng::new(0),
+            len: 0,
+        }
+    }
+}
+
+impl SkipListStatus {
+    fn entry(&self, node: usize) -> &LineWithOrd {
+        self.nodes[node].entry.as_ref().unwrap()
+    }
+
+    // The last node on every level whose entry is < key (or <= key if `inclusive`).
+    fn path(&self, key: &LineWithOrd, inclusive: bool) -> [usize; MAX_LEVEL] {
+        let mut path = [0; MAX_LEVEL];
+        let mut node = 0;
+        for level in (0..MAX_LEVEL).rev() {
+            loop {
+                let next = self.nodes[node].next[level];
+                if next == NIL {
+                    break;
+                }
+                match self.entry(next).cmp(key) {
+                    Ordering::Less => node = next,
+                    Ordering::Equal if inclusive => node = next,
+                    _ => break,
+                }
+            }
+            path[level] = node;
+        }
+        path
+    }
+
+    fn find(&self, key: &LineWithOrd) -> Option<usize> {
+        let next = self.nodes[self.path(key, false)[0]].next[0];
+        (next != NIL && self.entry(next) == key).then_some(next)
+    }
+
+    fn random_level(&mut self) -> usize {
+        (self.rng.next_u64().trailing_ones() as usize + 1).min(MAX_LEVEL)
+    }
+}
+
+impl SweepStatus for SkipListStatus {
+    fn insert(&mut self, entry: LineWithOrd) {
+        let path = self.path(&entry, false);
+        let level = self.random_level();
+        let next = path[..level]
+            .iter()
+            .enumerate()
+            .map(|(l, &node)| self.nodes[node].next[l])
+            .collect();
+        let node = SkipNode {
+            entry: Some(entry),
+            next,
+        };
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.nodes[index] = node;
+                index
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        };
+        for (l, &node) in path[..level].iter().enumerate() {
+            self.nodes[node].next[l] = index;
+        }
+        self.len += 1;
+    }
+
+    fn remove(&mut self, entry: &LineWithOrd) -> bool {
+        let path = self.path(entry, false);
+        let target = self.nodes[path[0]].next[0];
+        if target == NIL || self.entry(target) != entry {
+            return false;
+        }
+        for (l, &node) in path.iter().enumerate().take(self.nodes[target].next.len()) {
+            self.nodes[node].next[l] = self.nodes[target].next[l];
+        }
+        self.nodes[target].entry = None;
+        self.free.push(target);
+        self.len -= 1;
+        true
+    }
+
+    fn contains(&self, entry: &LineWithOrd) -> bool {
+        self.find(entry).is_some()
+    }
+
+    fn ceiling(&self, key: &LineWithOrd) -> Option<&LineWithOrd> {
+        let next = self.nodes[self.path(key, false)[0]].next[0];
+        (next != NIL).then(|| self.entry(next))
+    }
+
+    fn predecessor(&self, key: &LineWithOrd) -> Option<&LineWithOrd> {
+        self.nodes[self.path(key, false)[0]].entry.as_ref()
+    }
+
+    fn successor(&self, key: &LineWithOrd) -> Option<&LineWithOrd> {
+        let next = self.nodes[self.path(key, true)[0]].next[0];
+        (next != NIL).then(|| self.entry(next))
+    }
+
+    fn range(&self, low: &LineWithOrd, high: &LineWithOrd) -> Vec<&LineWithOrd> {
+        let mut entries = Vec::new();
+        let mut node = self.nodes[self.path(low, false)[0]].next[0];
+        while node != NIL && self.entry(node) <= high {
+            entries.push(self.entry(node));
+            node = self.nodes[node].next[0];
+        }
+        entries
+    }
+
+    fn swap(&mut self, lower: &LineWithOrd, upper: &LineWithOrd) {
+        if let (Some(a), Some(b)) = (self.find(lower), self.find(upper)) {
+            let entry = self.nodes[a].entry.take();
+            self.nodes[a].entry = self.nodes[b].entry.take();
+            self.nodes[b].entry = entry;
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+struct TreapNode {
+    entry: LineWithOrd,
+    priority: u64,
+    left: usize,
+    right: usize,
+}
+
+// Treap with nodes in an arena, insert and remove split and merge by the comparator.
+pub struct TreapStatus {
+    nodes: Vec<Option<TreapNode>>,
+    free: Vec<usize>,
+    root: usize,
+    rng: Rng,
+    len: usize,
+}
+
+impl Default for TreapStatus {
+    fn default() -> Self {
+        Self {
+            nodes: Vec::new(),
+            free: Vec::new(),
+            root: NIL,
+            rng: Rng::new(0),
+            len: 0,
+        }
+    }
+}
+
+impl TreapStatus {
+    fn node(&self, index: usize) -> &TreapNode {
+        self.nodes[index].as_ref().unwrap()
+    }
+
+    fn node_mut(&mut self, index: usize) -> &mut TreapNode {
+        self.nodes[index].as_mut().unwrap()
+    }
+
+    // Splits the tree into the entries < key (or <= key if `inclusive`) and the rest.
+    fn split(&mut self, tree: usize, key: &LineWithOrd, inclusive: bool) -> (usize, usize) {
+        if tree == NIL {
+            return (NIL, NIL);
+        }
+        let goes_left = match self.node(tree).entry.cmp(key) {
+            Ordering::Less => true,
+            Ordering::Equal => inclusive,
+            Ordering::Greater => false,
+        };
+        if goes_left {
+            let (left, right) = self.split(self.node(tree).right, key, inclusive);
+            self.node_mut(tree).right = left;
+            (tree, right)
+        } else {
+            let (left, right) = self.split(self.node(tree).left, key, inclusive);
+            self.node_mut(tree).left = right;
+            (left, tree)
+        }
+    }
+
+    // All entries of `left` are smaller than the ones of `right`.
+    fn merge(&mut self, left: usize, right: usize) -> usize {
+        if left == NIL {
+            return right;
+        }
+        if right == NIL {
+            return left;
+        }
+        if self.node(left).priority > self.node(right).priority {
+            let merged = self.merge(self.node(left).right, right);
+            self.node_mut(left).right = merged;
+            left
+        } else {
+            let merged = self.merge(left, self.node(right).left);
+            self.node_mut(right).left = merged;
+            right
+        }
+    }
+
+    fn find(&self, key: &LineWithOrd) -> Option<usize> {
+        let mut tree = self.root;
+        while tree != NIL {
+            let node = self.node(tree);
+            tree = match node.entry.cmp(key) {
+                Ordering::Less => node.right,
+                Ordering::Equal => return Some(tree),
+                Ordering::Greater => node.left,
+            };
+        }
+        None
+    }
+
+    // in order, subtrees entirely below `low` or above `high` are skipped
+    fn collect<'a>(
+        &'a self,
+        tree: usize,
+        low: &LineWithOrd,
+        high: &LineWithOrd,
+        entries: &mut Vec<&'a LineWithOrd>,
+    ) {
+        if tree == NIL {
+            return;
+        }
+        let node = self.node(tree);
+        let above_low = node.entry >= *low;
+        let below_high = node.entry <= *high;
+        if above_low {
+            self.collect(node.left, low, high, entries);
+        }
+        if above_low && below_high {
+            entries.push(&node.entry);
+        }
+        if below_high {
+            self.collect(node.right, low, high, entries);
+        }
+    }
+
+    // the smallest entry that is > key (or >= key if `inclusive`)
+    fn upper(&self, key: &LineWithOrd, inclusive: bool) -> Option<&LineWithOrd> {
+        let mut tree = self.root;
+        let mut found = None;
+        while tree != NIL {
+            let node = self.node(tree);
+            let ordering = node.entry.cmp(key);
+            if ordering == Ordering::Greater || inclusive && ordering == Ordering::Equal {
+                found = Some(&node.entry);
+                tree = node.left;
+            } else {
+                tree = node.right;
+            }
+        }
+        found
+    }
+}
+
+impl SweepStatus for TreapStatus {
+    fn insert(&mut self, entry: LineWithOrd) {
+        let (left, right) = self.split(self.root, &entry, false);
+        let node = Some(TreapNode {
+            entry,
+            priority: self.rng.next_u64(),
+            left: NIL,
+            right: NIL,
+        });
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.nodes[index] = node;
+                index
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        };
+        let left = self.merge(left, index);
+        self.root = self.merge(left, right);
+        self.len += 1;
+    }
+
+    fn remove(&mut self, entry: &LineWithOrd) -> bool {
+        let (left, rest) = self.split(self.root, entry, false);
+        let (equal, right) = self.split(rest, entry, true);
+        self.root = self.merge(left, right);
+        if equal == NIL {
+            return false;
+        }
+        // entries are unique, so `equal` is a single node
+        self.nodes[equal] = None;
+        self.free.push(equal);
+        self.len -= 1;
+        true
+    }
+
+    fn contains(&self, entry: &LineWithOrd) -> bool {
+        self.find(entry).is_some()
+    }
+
+    fn ceiling(&self, key: &LineWithOrd) -> Option<&LineWithOrd> {
+        self.upper(key, true)
+    }
+
+    fn predecessor(&self, key: &LineWithOrd) -> Option<&LineWithOrd> {
+        let mut tree = self.root;
+        let mut found = None;
+        while tree != NIL {
+            let node = self.node(tree);
+            if node.entry < *key {
+                found = Some(&node.entry);
+                tree = node.right;
+            } else {
+                tree = node.left;
+            }
+        }
+        found
+    }
+
+    fn successor(&self, key: &LineWithOrd) -> Option<&LineWithOrd> {
+        self.upper(key, false)
+    }
+
+    fn range(&self, low: &LineWithOrd, high: &LineWithOrd) -> Vec<&LineWithOrd> {
+        let mut entries = Vec::new();
+        self.collect(self.root, low, high, &mut entries);
+        entries
+    }
+
+    fn swap(&mut self, lower: &LineWithOrd, upper: &LineWithOrd) {
+        if let (Some(a), Some(b)) = (self.find(lower), self.find(upper)) {
+            let mut a_node = self.nodes[a].take().unwrap();
+            std::mem::swap(&mut a_node.entry, &mut self.node_mut(b).entry);
+            self.nodes[a] = Some(a_node);
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lib::{
+        line::Line,
+        line_with_ord::{SharedPosition, shared_position},
+        point::Point,
+    };
+
+    // horizontal segments, ordered by their height
+    fn entry(position: &SharedPosition, id: usize) -> LineWithOrd {
+        let y = id as f64;
+        let line = Line::new(Point { x: -1.0, y }, Point { x: 1.0, y });
+        LineWithOrd::new(line, id, position.clone())
+    }
+
+    // bottom to top
+    fn ids(status: &impl SweepStatus, position: &SharedPosition) -> Vec<usize> {
+        let bottom = Line::new(Point { x: -1.0, y: -1E9 }, Point { x: 1.0, y: -1E9 });
+        let mut ids = Vec::new();
+        let mut next = status
+            .ceiling(&LineWithOrd::new(bottom, 0, position.clone()))
+            .cloned();
+        while let Some(current) = next {
+            ids.push(current.id);
+            next = status.successor(&current).cloned();
+        }
+        ids
+    }
+
+    fn against_model<S: SweepStatus>() {
+        let position = shared_position();
+        let mut status = S::default();
+        let mut model = BTreeSet::new();
+        let mut rng = Rng::new(3);
+        for _ in 0..2000 {
+            let id = rng.index(100);
+            let key = entry(&position, id);
+            if rng.index(3) == 0 {
+                assert_eq!(status.remove(&key), model.remove(&id));
+            } else if model.insert(id) {
+                status.insert(key.clone());
+            }
+            assert_eq!(status.len(), model.len());
+            assert_eq!(status.contains(&key), model.contains(&id));
+            let id_of = |e: Option<&LineWithOrd>| e.map(|e| e.id);
+            assert_eq!(
+                id_of(status.ceiling(&key)),
+                model.range(id..).next().copied()
+            );
+            assert_eq!(
+                id_of(status.predecessor(&key)),
+                model.range(..id).next_back().copied()
+            );
+            assert_eq!(
+                id_of(status.successor(&key)),
+                model.range(id + 1..).next().copied()
+            );
+            let ids: Vec<_> = status
+                .range(&key, &entry(&position, id + 10))
+                .iter()
+                .map(|e| e.id)
+                .collect();
+            assert_eq!(ids, model.range(id..=id + 10).copied().collect::<Vec<_>>());
+        }
+        assert_eq!(
+            ids(&status, &position),
+            model.iter().copied().collect::<Vec<_>>()
+        );
+    }
+
+    fn swaps_at_crossing<S: SweepStatus>() {
+        let position = shared_position();
+        let line = |id, y1, y2| {
+            let line = Line::new(Point { x: 0.0, y: y1 }, Point { x: 4.0, y: y2 });
+            LineWithOrd::new(line, id, position.clone())
+        };
+        let mut status = S::default();
+        for entry in [
+            line(0, -5.0, -5.0),
+            line(1, 0.0, 4.0),
+            line(2, 4.0, 0.0),
+            line(3, 5.0, 5.0),
+        ] {
+            status.insert(entry);
+        }
+        position.set(SweepPosition {
+            point: Point { x: 2.0, y: 2.0 },
+            after: false,
+        });
+        assert_eq!(ids(&status, &position), vec![0, 1, 2, 3]);
+        status.swap(&line(1, 0.0, 4.0), &line(2, 4.0, 0.0));
+        position.set(SweepPosition {
+            point: Point { x: 2.0, y: 2.0 },
+            after: true,
+        });
+        assert_eq!(ids(&status, &position), vec![0, 2, 1, 3]);
+        assert!(status.contains(&line(1, 0.0, 4.0)) && status.contains(&line(2, 4.0, 0.0)));
+    }
+
+    #[test]
+    fn backends_match_model() {
+        against_model::<BTreeStatus>();
+        against_model::<SkipListStatus>();
+        against_model::<TreapStatus>();
+    }
+
+    #[test]
+    fn backends_swap_at_crossing() {
+        swaps_at_crossing::<BTreeStatus>();
+        swaps_at_crossing::<SkipListStatus>();
+        swaps_at_crossing::<TreapStatus>();
+    }
+}
@@ -0,0 +1,146 @@
+use crate::lib::{
+    ccw::ccw,
+    point::Point,
+    polygon::{is_ccw, ring_signed_area},
+};
+
+// Removes the closing point and consecutive duplicates and orients the ring counter-clockwise.
+pub fn normalized_ring(ring: &[Point]) -> Vec<Point> {
+    let mut points: Vec<Point> = Vec::with_capacity(ring.len());
+    for point in ring {
+        if points.last() != Some(point) {
+            points.push(*point);
+        }
+    }
+    if points.len() > 1 && points.first() == points.last() {
+        points.pop();
+    }
+    if !is_ccw(&points) {
+        points.reverse();
+    }
+    points
+}
+
+pub fn is_convex(ring: &[Point]) -> bool {
+    let points = normalized_ring(ring);
+    let n = points.len();
+    n >= 3 && (0..n).all(|i| ccw(&points[i], &points[(i + 1) % n], &points[(i + 2) % n]) >= 0)
+}
+
+// Ear clipping, O(n^2). Returns triangles as indices into `normalized_ring(ring)`.
+pub fn triangulate(ring: &[Point]) -> Vec<[usize; 3]> {
+    let points = normalized_ring(ring);
+    let mut remaining: Vec<usize> = (0..points.len()).collect();
+    let mut triangles = Vec::new();
+
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let ear = (0..n).find(|&i| {
+            let (a, b, c) = (
+                remaining[(i + n - 1) % n],
+                remaining[i],
+                remaining[(i + 1) % n],
+            );
+            ccw(&points[a], &points[b], &points[c]) > 0
+                && !remaining.iter().any(|&k| {
+                    k != a
+                        && k != b
+                        && k != c
+                        && ccw(&points[a], &points[b], &points[k]) >= 0
+                        && ccw(&points[b], &points[c], &points[k]) >= 0
+                        && ccw(&points[c], &points[a], &points[k]) >= 0
+                })
+        });
+        match ear {
+            Some(i) => {
+                triangles.push([
+                    remaining[(i + n - 1) % n],
+                    remaining[i],
+                    remaining[(i + 1) % n],
+                ]);
+                remaining.remove(i);
+            }
+            None => {
+                // only collinear vertices left that can not form an ear
+                let collinear = (0..n).find(|&i| {
+                    ccw(
+                        &points[remaining[(i + n - 1) % n]],
+                        &points[remaining[i]],
+                        &points[remaining[(i + 1) % n]],
+                    ) == 0
+                });
+                match collinear {
+                    Some(i) => {
+                        remaining.remove(i);
+                    }
+                    None => break,
+                }
+            }
+        }
+    }
+    if remaining.len() == 3
+        && ccw(
+            &points[remaining[0]],
+            &points[remaining[1]],
+            &points[remaining[2]],
+        ) != 0
+    {
+        triangles.push([remaining[0], remaining[1], remaining[2]]);
+    }
+    triangles
+}
+
+// Hertel-Mehlhorn: triangulate and remove every diagonal that is not needed for convexity.
+// The result has at most four times the minimal number of convex pieces.
+pub fn convex_decomposition(ring: &[Point]) -> Vec<Vec<Point>> {
+    let points = normalized_ring(ring);
+    if is_convex(&points) {
+        return vec![points];
+    }
+    let mut pieces: Vec<Option<Vec<usize>>> = triangulate(&points)
+        .into_iter()
+        .map(|t| Some(t.to_vec()))
+        .collect();
+
+    let mut merged = true;
+    while merged {
+        merged = false;
+        for p in 0..pieces.len() {
+            for q in p + 1..pieces.len() {
+                let (Some(first), Some(second)) = (&pieces[p], &pieces[q]) else {
+                    continue;
+                };
+                if let Some(union) = merge_along_diagonal(first, second) {
+                    let union_points: Vec<Point> = union.iter().map(|&i| points[i]).collect();
+                    if is_convex(&union_points) && ring_signed_area(&union_points) > 0.0 {
+                        pieces[p] = Some(union);
+                        pieces[q] = None;
+                        merged = true;
+                    }
+                }
+            }
+        }
+    }
+
+    pieces
+        .into_iter()
+        .flatten()
+        .map(|piece| piece.into_iter().map(|i| points[i]).collect())
+        .collect()
+}
+
+// Joins two counter-clockwise pieces that share the edge u->v (in `first`) / v->u (in `second`).
+fn merge_along_diagonal(first: &[usize], second: &[usize]) -> Option<Vec<usize>> {
+    let n = first.len();
+    let m = second.len();
+    for i in 0..n {
+        let (u, v) = (first[i], first[(i + 1) % n]);
+        if let Some(j) = (0..m).find(|&j| second[j] == v && second[(j + 1) % m] == u) {
+            // walk `first` from v around to u, then `second` from u (exclusive) to v (exclusive)
+            let mut union: Vec<usize> = (1..=n).map(|k| first[(i + k) % n]).collect();
+            union.extend((2..m).map(|k| second[(j + k) % m]));
+            return Some(union);
+        }
+    }
+    None
+}
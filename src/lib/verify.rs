@@ -0,0 +1,281 @@
+use crate::lib::{
+    common::{EPSILON, SWEEP_TOLERANCE},
+    line::Line,
+    sweep::intersections,
+};
+use std::fmt;
+
+// Directions with a smaller sine of the angle between them count as almost parallel.
+const NEAR_PARALLEL: f64 = 1E-6;
+
+// Why the two methods can disagree about a pair, the first one that applies.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiscrepancyKind {
+    // an endpoint lies on the other segment (or a segment is a point)
+    Touching,
+    // both segments lie on one line
+    Collinear,
+    Vertical,
+    NearParallel,
+    Other,
+}
+
+impl fmt::Display for DiscrepancyKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            DiscrepancyKind::Touching => "touching",
+            DiscrepancyKind::Collinear => "collinear",
+            DiscrepancyKind::Vertical => "vertical",
+            DiscrepancyKind::NearParallel => "near-parallel",
+            DiscrepancyKind::Other => "other",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Method {
+    BruteForce,
+    Sweep,
+}
+
+impl fmt::Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Method::BruteForce => write!(f, "brute force"),
+            Method::Sweep => write!(f, "sweep"),
+        }
+    }
+}
+
+// A pair of segments only one of the methods reported.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Discrepancy {
+    pub segments: (usize, usize),
+    pub found_by: Method,
+    pub kind: DiscrepancyKind,
+}
+
+impl fmt::Display for Discrepancy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}: only {} ({})",
+            self.segments.0, self.segments.1, self.found_by, self.kind
+        )
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Report {
+    pub brute_force: usize,
+    pub sweep: usize,
+    pub discrepancies: Vec<Discrepancy>,
+}
+
+impl Report {
+    pub fn count(&self, kind: DiscrepancyKind) -> usize {
+        self.discrepancies.iter().filter(|d| d.kind == kind).count()
+    }
+}
+
+// All pairs (i, j) with i < j for which `Line::crosses` holds, in O(n²).
+pub fn brute_force(lines: &[Line]) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+    for i in 0..lines.len() {
+        for j in i + 1..lines.len() {
+            if lines[i].crosses(&lines[j]) {
+                pairs.push((i, j));
+            }
+        }
+    }
+    pairs
+}
+
+// Runs the brute force and the sweep on the same segments and lists every pair only one of them
+// found.
+pub fn cross_check(lines: &[Line]) -> Report {
+    let brute_force = brute_force(lines);
+    let sweep: Vec<_> = intersections(lines.iter().cloned())
+        .iter()
+        .map(|intersection| intersection.segments)
+        .collect();
+    Report {
+        brute_force: brute_force.len(),
+        sweep: sweep.len(),
+        discrepancies: compare(lines, &brute_force, &sweep),
+    }
+}
+
+// For fallible input like a `SegmentReader`, which is read completely first.
+pub fn try_brute_force<E>(
+    lines: impl IntoIterator<Item = Result<Line, E>>,
+) -> Result<Vec<(usize, usize)>, E> {
+    Ok(brute_force(
+        &lines.into_iter().collect::<Result<Vec<_>, E>>()?,
+    ))
+}
+
+pub fn try_cross_check<E>(lines: impl IntoIterator<Item = Result<Line, E>>) -> Result<Report, E> {
+    Ok(cross_check(
+        &lines.into_iter().collect::<Result<Vec<_>, E>>()?,
+    ))
+}
+
+// Pairs are (smaller index, larger index), in any order.
+pub fn compare(
+    lines: &[Line],
+    brute_force: &[(usize, usize)],
+    sweep: &[(usize, usize)],
+) -> Vec<Discrepancy> {
+    let mut brute_force = brute_force.to_vec();
+    let mut sweep = sweep.to_vec();
+    brute_force.sort_unstable();
+    sweep.sort_unstable();
+
+    let mut discrepancies = Vec::new();
+    let mut add = |pair: (usize, usize), found_by| {
+        discrepancies.push(Discrepancy {
+            segments: pair,
+            found_by,
+            kind: classify(&lines[pair.0], &lines[pair.1]),
+        })
+    };
+    let (mut i, mut j) = (0, 0);
+    while i < brute_force.len() || j < sweep.len() {
+        match (brute_force.get(i), sweep.get(j)) {
+            (Some(a), Some(b)) if a == b => {
+                i += 1;
+                j += 1;
+            }
+            (Some(&a), Some(&b)) if a < b => {
+                add(a, Method::BruteForce);
+                i += 1;
+            }
+            (Some(&a), None) => {
+                add(a, Method::BruteForce);
+                i += 1;
+            }
+            (_, Some(&b)) => {
+                add(b, Method::Sweep);
+                j += 1;
+            }
+            (None, None) => unreachable!(),
+        }
+    }
+    discrepancies
+}
+
+pub fn classify(a: &Line, b: &Line) -> DiscrepancyKind {
+    if a.length() < EPSILON || b.length() < EPSILON {
+        return DiscrepancyKind::Touching;
+    }
+    let on_line = |line: &Line, other: &Line| {
+        line.point_distance(other.p1).abs() <= SWEEP_TOLERANCE
+            && line.point_distance(other.p2).abs() <= SWEEP_TOLERANCE
+    };
+    if on_line(a, b) || on_line(b, a) {
+        return DiscrepancyKind::Collinear;
+    }
+    let on_segment = |line: &Line, other: &Line| {
+        [other.p1, other.p2]
+            .iter()
+            .any(|p| p.distance_to_segment(&line.p1, &line.p2) <= SWEEP_TOLERANCE)
+    };
+    if on_segment(a, b) || on_segment(b, a) {
+        return DiscrepancyKind::Touching;
+    }
+    if (a.p1.x - a.p2.x).abs() < EPSILON || (b.p1.x - b.p2.x).abs() < EPSILON {
+        return DiscrepancyKind::Vertical;
+    }
+    let (da, db) = (a.p2 - a.p1, b.p2 - b.p1);
+    let sine = (da.x * db.y - da.y * db.x).abs() / (a.length() * b.length());
+    if sine < NEAR_PARALLEL {
+        return DiscrepancyKind::NearParallel;
+    }
+    DiscrepancyKind::Other
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lib::{generator::SegmentGenerator, point::Point};
+
+    fn line(x1: f64, y1: f64, x2: f64, y2: f64) -> Line {
+        Line::new(Point { x: x1, y: y1 }, Point { x: x2, y: y2 })
+    }
+
+    #[test]
+    fn classifies_pairs() {
+        let base = line(0.0, 0.0, 4.0, 0.0);
+        assert_eq!(
+            classify(&base, &line(2.0, 0.0, 2.0, 0.0)),
+            DiscrepancyKind::Touching
+        );
+        assert_eq!(
+            classify(&base, &line(3.0, 0.0, 6.0, 0.0)),
+            DiscrepancyKind::Collinear
+        );
+        assert_eq!(
+            classify(&base, &line(2.0, 0.0, 3.0, 1.0)),
+            DiscrepancyKind::Touching
+        );
+        assert_eq!(
+            classify(&base, &line(2.0, -1.0, 2.0, 1.0)),
+            DiscrepancyKind::Vertical
+        );
+        assert_eq!(
+            classify(&base, &line(0.0, 1E-3, 4.0, 1E-3 + 1E-8)),
+            DiscrepancyKind::NearParallel
+        );
+        assert_eq!(
+            classify(&base, &line(1.0, -1.0, 3.0, 1.0)),
+            DiscrepancyKind::Other
+        );
+    }
+
+    #[test]
+    fn lists_pairs_only_one_method_found() {
+        let lines = vec![
+            line(0.0, 0.0, 4.0, 0.0),
+            line(2.0, -1.0, 2.0, 1.0),
+            line(1.0, -1.0, 3.0, 1.0),
+            line(3.0, 0.0, 6.0, 0.0),
+        ];
+        let discrepancies = compare(&lines, &[(0, 1), (0, 3)], &[(0, 3), (0, 2)]);
+        assert_eq!(
+            discrepancies,
+            vec![
+                Discrepancy {
+                    segments: (0, 1),
+                    found_by: Method::BruteForce,
+                    kind: DiscrepancyKind::Vertical,
+                },
+                Discrepancy {
+                    segments: (0, 2),
+                    found_by: Method::Sweep,
+                    kind: DiscrepancyKind::Other,
+                },
+            ]
+        );
+        assert_eq!(
+            discrepancies[0].to_string(),
+            "0 1: only brute force (vertical)"
+        );
+
+        let report = cross_check(&lines);
+        assert_eq!((report.brute_force, report.sweep), (4, 4));
+        assert!(report.discrepancies.is_empty());
+        let report = try_cross_check(lines.iter().cloned().map(Ok::<_, ()>)).unwrap();
+        assert_eq!((report.brute_force, report.sweep), (4, 4));
+        assert_eq!(
+            try_brute_force([Ok(lines[0].clone()), Err("broken")]),
+            Err("broken")
+        );
+
+        let mut generator =
+            SegmentGenerator::new(4, Point { x: 0.0, y: 0.0 }, Point { x: 100.0, y: 100.0 });
+        let report = cross_check(&generator.grid_snapped(300, 20.0, 5.0));
+        assert!(report.brute_force > 0 && report.discrepancies.is_empty());
+    }
+}
@@ -0,0 +1,376 @@
+use crate::lib::{
+    ccw::{ccw, crossing_point},
+    line::Line,
+    point::Point,
+    polygon::Polygon,
+};
+use ordered_float::NotNan;
+use std::{cmp::Reverse, collections::BinaryHeap, f64::consts::PI};
+
+// Visibility polygon of `query`, which has to lie strictly inside `polygon` (not in a hole).
+//
+// Angular sweep around the query point: the vertices are processed in counter-clockwise order,
+// edges are activated at their first and deactivated at their second endpoint, and the closest
+// active edge along the ray before and after every event gives the vertices of the result.
+// Finding the closest edge is a linear scan over the active edges => O(n log n + n * k).
+// A non-finite query point or vertex gives an empty polygon.
+pub fn visibility_polygon(polygon: &Polygon, query: &Point) -> Polygon {
+    let edges: Vec<(Point, Point)> = polygon
+        .rings()
+        .flat_map(|ring| ring.windows(2).map(|pair| (pair[0], pair[1])))
+        .filter(|(a, b)| a != b)
+        .collect();
+    let is_finite = |p: &Point| p.x.is_finite() && p.y.is_finite();
+    if edges.is_empty() || !is_finite(query) || !edges.iter().all(|(a, _)| is_finite(a)) {
+        return Polygon::new(Vec::new());
+    }
+
+    // long enough to leave the bounding box from every query point inside of it
+    let reach = polygon
+        .exterior
+        .iter()
+        .map(|p| p.distance(query))
+        .fold(0.0, f64::max)
+        * 4.0
+        + 1.0;
+    let angle = |p: &Point| (p.y - query.y).atan2(p.x - query.x);
+
+    // events: (angle, edge, starts here)
+    let mut events: Vec<(f64, usize, bool)> = Vec::new();
+    for (i, (a, b)) in edges.iter().enumerate() {
+        match ccw(query, a, b) {
+            1 => {
+                events.push((angle(a), i, true));
+                events.push((angle(b), i, false));
+            }
+            -1 => {
+                events.push((angle(b), i, true));
+                events.push((angle(a), i, false));
+            }
+            // edges pointing towards the query point are covered by their neighbours
+            _ => {}
+        }
+    }
+    let mut angles: Vec<f64> = events.iter().map(|e| e.0).collect();
+    angles.sort_by(f64::total_cmp);
+    angles.dedup();
+
+    // start with a ray that does not pass through any vertex
+    let start_angle = if angles.len() == 1 {
+        angles[0] + PI
+    } else {
+        let (gap, index) = (0..angles.len())
+            .map(|i| {
+                let next = angles[(i + 1) % angles.len()];
+                ((next - angles[i]).rem_euclid(2.0 * PI), i)
+            })
+            .max_by(|a, b| a.0.total_cmp(&b.0))
+            .unwrap();
+        angles[index] + gap / 2.0
+    };
+    let ray_end = |angle: f64| Point {
+        x: query.x + reach * angle.cos(),
+        y: query.y + reach * angle.sin(),
+    };
+
+    let mut active = vec![false; edges.len()];
+    let start_ray = Line::new(*query, ray_end(start_angle));
+    for (i, (a, b)) in edges.iter().enumerate() {
+        active[i] = start_ray.crosses(&Line::new(*a, *b));
+    }
+
+    events.sort_by(|a, b| {
+        let a_angle = (a.0 - start_angle).rem_euclid(2.0 * PI);
+        let b_angle = (b.0 - start_angle).rem_euclid(2.0 * PI);
+        // at the same angle the deactivations come first
+        a_angle.total_cmp(&b_angle).then(a.2.cmp(&b.2))
+    });
+
+    let closest = |active: &[bool], far: &Point| -> Option<(usize, Point)> {
+        let ray = Line::new(*query, *far);
+        edges
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| active[*i])
+            .filter(|(_, (a, b))| ray.crosses(&Line::new(*a, *b)))
+            .filter(|(_, (a, b))| ccw(query, far, a) != 0 || ccw(query, far, b) != 0)
+            .map(|(i, (a, b))| (i, crossing_point(query, far, a, b)))
+            .min_by(|(_, p), (_, q)| p.distance(query).total_cmp(&q.distance(query)))
+    };
+
+    let mut result: Vec<Point> = Vec::new();
+    let mut i = 0;
+    while i < events.len() {
+        let far = ray_end(events[i].0);
+        let mut j = i;
+        // all events on the same ray
+        while j < events.len() && (events[j].0 - events[i].0).abs() < 1e-12 {
+            j += 1;
+        }
+        let before = closest(&active, &far);
+        for &(_, edge, starts) in &events[i..j] {
+            active[edge] = starts;
+        }
+        let after = closest(&active, &far);
+        // as long as the same edge is the closest one, no vertex is needed
+        if before.map(|(edge, _)| edge) != after.map(|(edge, _)| edge) {
+            for (_, point) in [before, after].into_iter().flatten() {
+                if result
+                    .last()
+                    .is_none_or(|last| last.distance(&point) > 1e-9)
+                {
+                    result.push(point);
+                }
+            }
+        }
+        i = j;
+    }
+    if result.len() > 1 && result[0].distance(result.last().unwrap()) <= 1e-9 {
+        result.pop();
+    }
+    Polygon::new(result)
+}
+
+pub struct VisibilityGraph {
+    pub vertices: Vec<Point>,
+    // adjacency[i] = (neighbour, euclidean distance)
+    pub adjacency: Vec<Vec<(usize, f64)>>,
+}
+
+impl VisibilityGraph {
+    // Graph on the vertices of `polygon` (and `extra` points, e.g. start and goal) that connects
+    // every pair of points seeing each other inside the polygon.
+    pub fn new(polygon: &Polygon, extra: &[Point]) -> Self {
+        let edges: Vec<(Point, Point)> = polygon
+            .rings()
+            .flat_map(|ring| ring.windows(2).map(|pair| (pair[0], pair[1])))
+            .collect();
+        let vertices: Vec<Point> = polygon
+            .rings()
+            .flat_map(|ring| ring.iter().skip(1).copied())
+            .chain(extra.iter().copied())
+            .collect();
+        Self::build(vertices, &edges, |p| polygon.contains(p))
+    }
+
+    // Graph for a point robot moving between polygonal obstacles in the unbounded plane.
+    pub fn from_obstacles(obstacles: &[Polygon], extra: &[Point]) -> Self {
+        let edges: Vec<(Point, Point)> = obstacles
+            .iter()
+            .flat_map(|obstacle| obstacle.rings())
+            .flat_map(|ring| ring.windows(2).map(|pair| (pair[0], pair[1])))
+            .collect();
+        let vertices: Vec<Point> = obstacles
+            .iter()
+            .flat_map(|obstacle| obstacle.rings())
+            .flat_map(|ring| ring.iter().skip(1).copied())
+            .chain(extra.iter().copied())
+            .collect();
+        Self::build(vertices, &edges, |p| {
+            !obstacles.iter().any(|obstacle| obstacle.contains(p))
+        })
+    }
+
+    // O(n^3): every pair of vertices is tested against every edge.
+    fn build(
+        vertices: Vec<Point>,
+        edges: &[(Point, Point)],
+        is_free: impl Fn(&Point) -> bool,
+    ) -> Self {
+        let mut adjacency = vec![Vec::new(); vertices.len()];
+        for i in 0..vertices.len() {
+            for j in i + 1..vertices.len() {
+                let (u, v) = (&vertices[i], &vertices[j]);
+                if u == v {
+                    continue;
+                }
+                let is_boundary = edges
+                    .iter()
+                    .any(|(a, b)| (a == u && b == v) || (a == v && b == u));
+                if is_boundary || is_visible(u, v, &vertices, edges, &is_free) {
+                    let distance = u.distance(v);
+                    adjacency[i].push((j, distance));
+                    adjacency[j].push((i, distance));
+                }
+            }
+        }
+        Self {
+            vertices,
+            adjacency,
+        }
+    }
+
+    // Dijkstra, returns the length and the vertex indices of the shortest path.
+    pub fn shortest_path(&self, from: usize, to: usize) -> Option<(f64, Vec<usize>)> {
+        let mut distances = vec![f64::INFINITY; self.vertices.len()];
+        let mut previous = vec![usize::MAX; self.vertices.len()];
+        let mut heap = BinaryHeap::new();
+        distances[from] = 0.0;
+        heap.push(Reverse((NotNan::new(0.0).unwrap(), from)));
+
+        while let Some(Reverse((distance, vertex))) = heap.pop() {
+            let distance = distance.into_inner();
+            if vertex == to {
+                let mut path = vec![to];
+                while *path.last().unwrap() != from {
+                    path.push(previous[*path.last().unwrap()]);
+                }
+                path.reverse();
+                return Some((distance, path));
+            }
+            if distance > distances[vertex] {
+                continue;
+            }
+            for &(neighbour, length) in &self.adjacency[vertex] {
+                let candidate = distance + length;
+                if candidate < distances[neighbour] {
+                    distances[neighbour] = candidate;
+                    previous[neighbour] = vertex;
+                    heap.push(Reverse((NotNan::new(candidate).unwrap(), neighbour)));
+                }
+            }
+        }
+        None
+    }
+}
+
+// Shortest path between two points inside of `polygon`, avoiding its holes.
+pub fn shortest_path(polygon: &Polygon, start: &Point, goal: &Point) -> Option<(f64, Vec<Point>)> {
+    let graph = VisibilityGraph::new(polygon, &[*start, *goal]);
+    let n = graph.vertices.len();
+    graph.shortest_path(n - 2, n - 1).map(|(length, path)| {
+        (
+            length,
+            path.into_iter().map(|i| graph.vertices[i]).collect(),
+        )
+    })
+}
+
+fn is_visible(
+    u: &Point,
+    v: &Point,
+    vertices: &[Point],
+    edges: &[(Point, Point)],
+    is_free: &impl Fn(&Point) -> bool,
+) -> bool {
+    let segment = Line::new(*u, *v);
+    // a vertex inside of the segment: the path over that vertex is just as short
+    if vertices
+        .iter()
+        .any(|p| p != u && p != v && ccw(u, v, p) == 0 && p.is_in_boundary(&segment))
+    {
+        return false;
+    }
+    // proper crossings with the boundary
+    if edges
+        .iter()
+        .any(|(a, b)| ccw(u, v, a) * ccw(u, v, b) == -1 && ccw(a, b, u) * ccw(a, b, v) == -1)
+    {
+        return false;
+    }
+    is_free(&Point {
+        x: (u.x + v.x) / 2.0,
+        y: (u.y + v.y) / 2.0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn p(x: f64, y: f64) -> Point {
+        Point { x, y }
+    }
+
+    fn l_shape() -> Polygon {
+        Polygon::new(vec![
+            p(0.0, 0.0),
+            p(4.0, 0.0),
+            p(4.0, 2.0),
+            p(2.0, 2.0),
+            p(2.0, 4.0),
+            p(0.0, 4.0),
+        ])
+    }
+
+    #[test]
+    fn convex_room_is_completely_visible() {
+        let room = Polygon::new(vec![p(0.0, 0.0), p(4.0, 0.0), p(4.0, 3.0), p(0.0, 3.0)]);
+        let visible = visibility_polygon(&room, &p(1.0, 1.0));
+        assert!((visible.area() - 12.0).abs() < 1e-9);
+        assert_eq!(visible.vertex_count(), 4);
+    }
+
+    #[test]
+    fn reflex_vertex_casts_a_shadow() {
+        let room = l_shape();
+        let visible = visibility_polygon(&room, &p(3.0, 0.5));
+        // the ray over the reflex vertex (2, 2) leaves the upper arm at (2/3, 4)
+        assert!((visible.area() - (12.0 - 4.0 / 3.0)).abs() < 1e-9);
+        assert!(visible.contains(&p(1.0, 2.5)));
+        assert!(!visible.contains(&p(1.5, 3.9)));
+    }
+
+    #[test]
+    fn ray_touching_collinear_vertices() {
+        // the ray over the reflex vertex (2, 2) ends in the vertex (0, 4)
+        let visible = visibility_polygon(&l_shape(), &p(3.0, 1.0));
+        assert!((visible.area() - 10.0).abs() < 1e-9);
+        assert!(!visible.contains(&p(1.5, 3.5)));
+
+        // a vertex in the middle of an edge lies on the ray to a corner
+        let room = Polygon::new(vec![
+            p(0.0, 0.0),
+            p(2.0, 0.0),
+            p(4.0, 0.0),
+            p(4.0, 4.0),
+            p(0.0, 4.0),
+        ]);
+        let visible = visibility_polygon(&room, &p(1.0, 2.0));
+        assert!((visible.area() - 16.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn dijkstra_around_an_obstacle() {
+        let obstacle = Polygon::new(vec![p(1.0, 1.0), p(3.0, 1.0), p(3.0, 3.0), p(1.0, 3.0)]);
+        let graph = VisibilityGraph::from_obstacles(&[obstacle], &[p(0.0, 2.0), p(4.0, 2.0)]);
+        let n = graph.vertices.len();
+        let (length, path) = graph.shortest_path(n - 2, n - 1).unwrap();
+        assert!((length - (2.0 + 2.0 * 2.0_f64.sqrt())).abs() < 1e-9);
+        // over two corners of the same side of the obstacle
+        assert_eq!(path.len(), 4);
+        let corners: Vec<Point> = path[1..3].iter().map(|&i| graph.vertices[i]).collect();
+        assert!(corners == [p(1.0, 1.0), p(3.0, 1.0)] || corners == [p(1.0, 3.0), p(3.0, 3.0)]);
+    }
+
+    #[test]
+    fn shortest_path_in_polygon_with_hole() {
+        let room = Polygon::with_holes(
+            vec![p(0.0, 0.0), p(4.0, 0.0), p(4.0, 4.0), p(0.0, 4.0)],
+            vec![vec![p(1.0, 1.0), p(1.0, 3.0), p(3.0, 3.0), p(3.0, 1.0)]],
+        );
+        let (length, path) = shortest_path(&room, &p(0.5, 2.0), &p(3.5, 2.0)).unwrap();
+        assert!((length - (2.0 + 2.0 * 1.25_f64.sqrt())).abs() < 1e-9);
+        assert_eq!(path.len(), 4);
+
+        // seeing each other directly
+        let (length, path) = shortest_path(&room, &p(0.5, 0.5), &p(3.5, 0.5)).unwrap();
+        assert!((length - 3.0).abs() < 1e-9);
+        assert_eq!(path, vec![p(0.5, 0.5), p(3.5, 0.5)]);
+    }
+
+    #[test]
+    fn non_finite_coordinates() {
+        assert_eq!(
+            visibility_polygon(&l_shape(), &p(f64::NAN, 1.0)).vertex_count(),
+            0
+        );
+        let room = Polygon::new(vec![
+            p(0.0, 0.0),
+            p(4.0, 0.0),
+            p(f64::NAN, 3.0),
+            p(0.0, 3.0),
+        ]);
+        assert_eq!(visibility_polygon(&room, &p(1.0, 1.0)).vertex_count(), 0);
+    }
+}
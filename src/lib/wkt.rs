@@ -0,0 +1,610 @@
+use crate::lib::{
+    line::Line,
+    point::Point,
+    polygon::{MultiPolygon, Polygon},
+};
+use std::{error::Error, fmt};
+
+// Well-Known Text and Well-Known Binary (OGC simple features), 2D only.
+// `POINT EMPTY` is a point with NaN coordinates, as in the usual WKB encoding.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Geometry {
+    Point(Point),
+    LineString(Vec<Point>),
+    Polygon(Polygon),
+    MultiPolygon(MultiPolygon),
+    GeometryCollection(Vec<Geometry>),
+}
+
+// Deeper nested collections are rejected, so malicious input can not overflow the stack.
+const MAX_DEPTH: usize = 64;
+
+// Reading WKT or WKB failed. `position` is the byte offset in the input.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WktError {
+    pub position: usize,
+    pub reason: String,
+}
+
+impl fmt::Display for WktError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "at byte {}: {}", self.position, self.reason)
+    }
+}
+
+impl Error for WktError {}
+
+impl From<Point> for Geometry {
+    fn from(point: Point) -> Self {
+        Geometry::Point(point)
+    }
+}
+
+impl From<&Line> for Geometry {
+    fn from(line: &Line) -> Self {
+        Geometry::LineString(vec![line.p1, line.p2])
+    }
+}
+
+impl From<Polygon> for Geometry {
+    fn from(polygon: Polygon) -> Self {
+        Geometry::Polygon(polygon)
+    }
+}
+
+impl Geometry {
+    // A segment set as collection of two-point line strings.
+    pub fn from_lines(lines: &[Line]) -> Self {
+        Geometry::GeometryCollection(lines.iter().map(Geometry::from).collect())
+    }
+
+    // All segments of all line strings (also inside collections).
+    pub fn lines(&self) -> Vec<Line> {
+        match self {
+            Geometry::LineString(points) => points
+                .windows(2)
+                .map(|pair| Line::new(pair[0], pair[1]))
+                .collect(),
+            Geometry::GeometryCollection(geometries) => {
+                geometries.iter().flat_map(Geometry::lines).collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    // All polygons (also inside multi polygons and collections).
+    pub fn polygons(&self) -> MultiPolygon {
+        match self {
+            Geometry::Polygon(polygon) => vec![polygon.clone()],
+            Geometry::MultiPolygon(polygons) => polygons.clone(),
+            Geometry::GeometryCollection(geometries) => {
+                geometries.iter().flat_map(Geometry::polygons).collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn from_wkt(text: &str) -> Result<Self, WktError> {
+        let mut parser = WktParser {
+            text,
+            position: 0,
+            depth: 0,
+        };
+        let geometry = parser.geometry()?;
+        parser.skip_whitespace();
+        if parser.position < text.len() {
+            return Err(parser.error("unexpected text after the geometry"));
+        }
+        Ok(geometry)
+    }
+
+    pub fn to_wkt(&self) -> String {
+        let mut wkt = String::new();
+        self.write_wkt(&mut wkt);
+        wkt
+    }
+
+    pub fn from_wkb(bytes: &[u8]) -> Result<Self, WktError> {
+        let mut reader = WkbReader {
+            bytes,
+            position: 0,
+            little_endian: true,
+            depth: 0,
+        };
+        let geometry = reader.geometry()?;
+        if reader.position < bytes.len() {
+            return Err(reader.error("unexpected bytes after the geometry"));
+        }
+        Ok(geometry)
+    }
+
+    // Always written little-endian.
+    pub fn to_wkb(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.write_wkb(&mut bytes);
+        bytes
+    }
+
+    fn write_wkt(&self, wkt: &mut String) {
+        match self {
+            Geometry::Point(p) if p.x.is_nan() && p.y.is_nan() => wkt.push_str("POINT EMPTY"),
+            Geometry::Point(p) => wkt.push_str(&format!("POINT ({} {})", p.x, p.y)),
+            Geometry::LineString(points) => {
+                wkt.push_str("LINESTRING ");
+                write_wkt_points(points, wkt);
+            }
+            Geometry::Polygon(polygon) => {
+                wkt.push_str("POLYGON ");
+                write_wkt_polygon(polygon, wkt);
+            }
+            Geometry::MultiPolygon(polygons) => {
+                wkt.push_str("MULTIPOLYGON ");
+                write_wkt_list(polygons, wkt, write_wkt_polygon);
+            }
+            Geometry::GeometryCollection(geometries) => {
+                wkt.push_str("GEOMETRYCOLLECTION ");
+                write_wkt_list(geometries, wkt, Geometry::write_wkt);
+            }
+        }
+    }
+
+    fn write_wkb(&self, bytes: &mut Vec<u8>) {
+        // byte order: 1 = little-endian
+        bytes.push(1);
+        match self {
+            Geometry::Point(p) => {
+                bytes.extend(1u32.to_le_bytes());
+                write_wkb_point(p, bytes);
+            }
+            Geometry::LineString(points) => {
+                bytes.extend(2u32.to_le_bytes());
+                write_wkb_points(points, bytes);
+            }
+            Geometry::Polygon(polygon) => {
+                bytes.extend(3u32.to_le_bytes());
+                write_wkb_polygon(polygon, bytes);
+            }
+            Geometry::MultiPolygon(polygons) => {
+                bytes.extend(6u32.to_le_bytes());
+                bytes.extend((polygons.len() as u32).to_le_bytes());
+                for polygon in polygons {
+                    bytes.push(1);
+                    bytes.extend(3u32.to_le_bytes());
+                    write_wkb_polygon(polygon, bytes);
+                }
+            }
+            Geometry::GeometryCollection(geometries) => {
+                bytes.extend(7u32.to_le_bytes());
+                bytes.extend((geometries.len() as u32).to_le_bytes());
+                for geometry in geometries {
+                    geometry.write_wkb(bytes);
+                }
+            }
+        }
+    }
+}
+
+fn write_wkt_list<T>(items: &[T], wkt: &mut String, write: impl Fn(&T, &mut String)) {
+    if items.is_empty() {
+        wkt.push_str("EMPTY");
+        return;
+    }
+    wkt.push('(');
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            wkt.push_str(", ");
+        }
+        write(item, wkt);
+    }
+    wkt.push(')');
+}
+
+fn write_wkt_points(points: &[Point], wkt: &mut String) {
+    write_wkt_list(points, wkt, |p, wkt| {
+        wkt.push_str(&format!("{} {}", p.x, p.y))
+    });
+}
+
+fn write_wkt_polygon(polygon: &Polygon, wkt: &mut String) {
+    if polygon.exterior.is_empty() {
+        wkt.push_str("EMPTY");
+        return;
+    }
+    let rings: Vec<&Vec<Point>> = polygon.rings().collect();
+    write_wkt_list(&rings, wkt, |ring, wkt| write_wkt_points(ring, wkt));
+}
+
+fn write_wkb_point(p: &Point, bytes: &mut Vec<u8>) {
+    bytes.extend(p.x.to_le_bytes());
+    bytes.extend(p.y.to_le_bytes());
+}
+
+fn write_wkb_points(points: &[Point], bytes: &mut Vec<u8>) {
+    bytes.extend((points.len() as u32).to_le_bytes());
+    for p in points {
+        write_wkb_point(p, bytes);
+    }
+}
+
+fn write_wkb_polygon(polygon: &Polygon, bytes: &mut Vec<u8>) {
+    if polygon.exterior.is_empty() {
+        bytes.extend(0u32.to_le_bytes());
+        return;
+    }
+    bytes.extend((polygon.holes.len() as u32 + 1).to_le_bytes());
+    for ring in polygon.rings() {
+        write_wkb_points(ring, bytes);
+    }
+}
+
+struct WktParser<'a> {
+    text: &'a str,
+    position: usize,
+    depth: usize,
+}
+
+impl WktParser<'_> {
+    fn error(&self, reason: &str) -> WktError {
+        WktError {
+            position: self.position,
+            reason: reason.to_string(),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.text[self.position..];
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    fn word(&mut self) -> String {
+        self.skip_whitespace();
+        let rest = &self.text[self.position..];
+        let length = rest
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(rest.len());
+        self.position += length;
+        rest[..length].to_ascii_uppercase()
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), WktError> {
+        self.skip_whitespace();
+        if self.text[self.position..].starts_with(c) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", c)))
+        }
+    }
+
+    // Consumes `c` if it comes next.
+    fn accept(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        let found = self.text[self.position..].starts_with(c);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    // Either EMPTY (=> false) or an opening parenthesis (=> true).
+    fn open(&mut self) -> Result<bool, WktError> {
+        self.skip_whitespace();
+        let start = self.position;
+        if self.word() == "EMPTY" {
+            return Ok(false);
+        }
+        self.position = start;
+        self.expect('(')?;
+        Ok(true)
+    }
+
+    // "( item, item, ... )" or EMPTY
+    fn list<T>(
+        &mut self,
+        mut item: impl FnMut(&mut Self) -> Result<T, WktError>,
+    ) -> Result<Vec<T>, WktError> {
+        let mut items = Vec::new();
+        if !self.open()? {
+            return Ok(items);
+        }
+        loop {
+            items.push(item(self)?);
+            if !self.accept(',') {
+                break;
+            }
+        }
+        self.expect(')')?;
+        Ok(items)
+    }
+
+    fn number(&mut self) -> Result<f64, WktError> {
+        self.skip_whitespace();
+        let rest = &self.text[self.position..];
+        let length = rest
+            .find(|c: char| !(c.is_ascii_digit() || matches!(c, '+' | '-' | '.' | 'e' | 'E')))
+            .unwrap_or(rest.len());
+        let number = rest[..length]
+            .parse::<f64>()
+            .map_err(|_| self.error("expected a number"))?;
+        self.position += length;
+        Ok(number)
+    }
+
+    fn point(&mut self) -> Result<Point, WktError> {
+        Ok(Point {
+            x: self.number()?,
+            y: self.number()?,
+        })
+    }
+
+    fn points(&mut self) -> Result<Vec<Point>, WktError> {
+        self.list(Self::point)
+    }
+
+    fn polygon(&mut self) -> Result<Polygon, WktError> {
+        let mut rings = self.list(Self::points)?.into_iter();
+        Ok(match rings.next() {
+            Some(exterior) => Polygon::with_holes(exterior, rings.collect()),
+            None => Polygon::new(Vec::new()),
+        })
+    }
+
+    fn geometry(&mut self) -> Result<Geometry, WktError> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error("geometries are nested too deeply"));
+        }
+        self.depth += 1;
+        let start = self.position;
+        let geometry = match self.word().as_str() {
+            "POINT" => {
+                if !self.open()? {
+                    Geometry::Point(Point {
+                        x: f64::NAN,
+                        y: f64::NAN,
+                    })
+                } else {
+                    let point = self.point()?;
+                    self.expect(')')?;
+                    Geometry::Point(point)
+                }
+            }
+            "LINESTRING" => Geometry::LineString(self.points()?),
+            "POLYGON" => Geometry::Polygon(self.polygon()?),
+            "MULTIPOLYGON" => Geometry::MultiPolygon(self.list(Self::polygon)?),
+            "GEOMETRYCOLLECTION" => Geometry::GeometryCollection(self.list(Self::geometry)?),
+            "" => return Err(self.error("expected a geometry type")),
+            other => {
+                return Err(WktError {
+                    position: start,
+                    reason: format!("unsupported geometry type {}", other),
+                });
+            }
+        };
+        self.depth -= 1;
+        Ok(geometry)
+    }
+}
+
+struct WkbReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    little_endian: bool,
+    depth: usize,
+}
+
+impl WkbReader<'_> {
+    fn error(&self, reason: &str) -> WktError {
+        WktError {
+            position: self.position,
+            reason: reason.to_string(),
+        }
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], WktError> {
+        let bytes = self
+            .bytes
+            .get(self.position..self.position + N)
+            .ok_or_else(|| self.error("unexpected end of data"))?;
+        self.position += N;
+        Ok(bytes.try_into().unwrap())
+    }
+
+    fn u32(&mut self) -> Result<u32, WktError> {
+        let bytes = self.take::<4>()?;
+        Ok(if self.little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        })
+    }
+
+    fn f64(&mut self) -> Result<f64, WktError> {
+        let bytes = self.take::<8>()?;
+        Ok(if self.little_endian {
+            f64::from_le_bytes(bytes)
+        } else {
+            f64::from_be_bytes(bytes)
+        })
+    }
+
+    // element count, checked against the remaining data so corrupt input can not allocate
+    // huge vectors
+    fn count(&mut self, minimal_size: usize) -> Result<usize, WktError> {
+        let count = self.u32()? as usize;
+        if count * minimal_size > self.bytes.len() - self.position {
+            return Err(self.error("count exceeds the data"));
+        }
+        Ok(count)
+    }
+
+    fn point(&mut self) -> Result<Point, WktError> {
+        Ok(Point {
+            x: self.f64()?,
+            y: self.f64()?,
+        })
+    }
+
+    fn points(&mut self) -> Result<Vec<Point>, WktError> {
+        let count = self.count(16)?;
+        (0..count).map(|_| self.point()).collect()
+    }
+
+    fn polygon(&mut self) -> Result<Polygon, WktError> {
+        let count = self.count(4)?;
+        let mut rings = (0..count)
+            .map(|_| self.points())
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter();
+        Ok(match rings.next() {
+            Some(exterior) => Polygon::with_holes(exterior, rings.collect()),
+            None => Polygon::new(Vec::new()),
+        })
+    }
+
+    fn geometry(&mut self) -> Result<Geometry, WktError> {
+        self.little_endian = match self.take::<1>()?[0] {
+            0 => false,
+            1 => true,
+            _ => return Err(self.error("invalid byte order")),
+        };
+        if self.depth == MAX_DEPTH {
+            return Err(self.error("geometries are nested too deeply"));
+        }
+        self.depth += 1;
+        let start = self.position;
+        let geometry = match self.u32()? {
+            1 => Geometry::Point(self.point()?),
+            2 => Geometry::LineString(self.points()?),
+            3 => Geometry::Polygon(self.polygon()?),
+            6 => {
+                let count = self.count(9)?;
+                let mut polygons = Vec::with_capacity(count);
+                for _ in 0..count {
+                    match self.geometry()? {
+                        Geometry::Polygon(polygon) => polygons.push(polygon),
+                        _ => return Err(self.error("expected a polygon")),
+                    }
+                }
+                Geometry::MultiPolygon(polygons)
+            }
+            7 => {
+                let count = self.count(5)?;
+                let geometries = (0..count)
+                    .map(|_| self.geometry())
+                    .collect::<Result<_, _>>()?;
+                Geometry::GeometryCollection(geometries)
+            }
+            other => {
+                return Err(WktError {
+                    position: start,
+                    reason: format!("unsupported geometry type {}", other),
+                });
+            }
+        };
+        self.depth -= 1;
+        Ok(geometry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn p(x: f64, y: f64) -> Point {
+        Point { x, y }
+    }
+
+    fn examples() -> Vec<Geometry> {
+        let square = Polygon::with_holes(
+            vec![p(0.0, 0.0), p(10.0, 0.0), p(10.0, 10.0), p(0.0, 10.0)],
+            vec![vec![p(2.0, 2.0), p(2.0, 4.0), p(4.0, 4.0)]],
+        );
+        let triangle = Polygon::new(vec![p(20.0, 0.0), p(30.0, 0.0), p(25.0, 0.1)]);
+        vec![
+            Geometry::Point(p(1.5, -2.25e-7)),
+            Geometry::from(&Line::new(p(0.1, 0.2), p(0.3, 0.4))),
+            Geometry::Polygon(square.clone()),
+            Geometry::MultiPolygon(vec![square, triangle.clone()]),
+            Geometry::GeometryCollection(vec![
+                Geometry::Point(p(0.0, 1.0)),
+                Geometry::LineString(Vec::new()),
+                Geometry::MultiPolygon(vec![triangle]),
+            ]),
+        ]
+    }
+
+    #[test]
+    fn wkt_round_trip() {
+        for geometry in examples() {
+            let wkt = geometry.to_wkt();
+            assert_eq!(Geometry::from_wkt(&wkt).unwrap(), geometry, "{}", wkt);
+        }
+    }
+
+    #[test]
+    fn wkb_round_trip() {
+        for geometry in examples() {
+            assert_eq!(Geometry::from_wkb(&geometry.to_wkb()).unwrap(), geometry);
+        }
+    }
+
+    #[test]
+    fn reading() {
+        let geometry = Geometry::from_wkt(
+            "geometrycollection(LINESTRING(0 0,1 1, 2 0),POLYGON ((0 0, 1 0, 0 1, 0 0)))",
+        )
+        .unwrap();
+        assert_eq!(geometry.lines().len(), 2);
+        assert_eq!(geometry.polygons()[0].area(), 0.5);
+
+        // big-endian point
+        let mut bytes = vec![0];
+        bytes.extend(1u32.to_be_bytes());
+        bytes.extend(3.0f64.to_be_bytes());
+        bytes.extend(4.0f64.to_be_bytes());
+        assert_eq!(
+            Geometry::from_wkb(&bytes).unwrap(),
+            Geometry::Point(p(3.0, 4.0))
+        );
+
+        let error = Geometry::from_wkt("POLYGON ((0 0, 1 0, 0 1 0 0))").unwrap_err();
+        assert_eq!(error.position, 24);
+        assert!(Geometry::from_wkt("CIRCULARSTRING (0 0, 1 1, 2 0)").is_err());
+        assert!(Geometry::from_wkb(&bytes[..10]).is_err());
+    }
+
+    #[test]
+    fn empty_point() {
+        for geometry in [
+            Geometry::from_wkt("POINT EMPTY").unwrap(),
+            Geometry::from_wkb(&Geometry::from_wkt("point empty").unwrap().to_wkb()).unwrap(),
+        ] {
+            let Geometry::Point(point) = geometry else {
+                panic!("expected a point, got {:?}", geometry);
+            };
+            assert!(point.x.is_nan() && point.y.is_nan());
+            assert_eq!(geometry.to_wkt(), "POINT EMPTY");
+        }
+    }
+
+    #[test]
+    fn nesting_is_limited() {
+        let depth = 100_000;
+        let wkt = "GEOMETRYCOLLECTION (".repeat(depth) + "POINT (0 0)" + &")".repeat(depth);
+        let error = Geometry::from_wkt(&wkt).unwrap_err();
+        assert_eq!(error.reason, "geometries are nested too deeply");
+
+        let mut bytes = Vec::new();
+        for _ in 0..depth {
+            bytes.push(1);
+            bytes.extend(7u32.to_le_bytes());
+            bytes.extend(1u32.to_le_bytes());
+        }
+        bytes.extend(Geometry::Point(p(0.0, 0.0)).to_wkb());
+        let error = Geometry::from_wkb(&bytes).unwrap_err();
+        assert_eq!(error.reason, "geometries are nested too deeply");
+
+        let wkt = "GEOMETRYCOLLECTION (".repeat(10) + "POINT (0 0)" + &")".repeat(10);
+        assert!(Geometry::from_wkt(&wkt).is_ok());
+    }
+}